@@ -0,0 +1,378 @@
+//! Print out levels on paper.
+//!
+//! ```text
+//! crop-circles-book <puzzles.json5 or pack> [--per-page N] [--format html|svg] [--out PATH]
+//! ```
+//!
+//! Puzzles come first with their difficulty in stars, then an answer key
+//! with the solver's solutions.
+
+use std::{fmt::Write, fs, path::PathBuf};
+
+use crop_circles_core::{
+    puzzle::{Board, Level},
+    svg::Drawing,
+};
+use eyre::{bail, eyre, WrapErr};
+use serde::Deserialize;
+
+/// A4, in mm
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const PAGE_MARGIN: f32 = 15.0;
+const HEADER_HEIGHT: f32 = 15.0;
+const TITLE_HEIGHT: f32 = 8.0;
+const GAP: f32 = 6.0;
+
+const INK: &str = "#42756b";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LevelFile {
+    Pack {
+        name: String,
+        #[serde(default)]
+        author: String,
+        rows: Vec<Vec<Level>>,
+    },
+    Main(Vec<Vec<Level>>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Html,
+    Svg,
+}
+
+struct Args {
+    input: PathBuf,
+    per_page: usize,
+    format: Format,
+    out: Option<PathBuf>,
+}
+
+fn main() -> eyre::Result<()> {
+    let args = parse_args(std::env::args().skip(1))?;
+
+    let src = fs::read_to_string(&args.input)
+        .wrap_err_with(|| format!("couldn't read {}", args.input.display()))?;
+    let file: LevelFile = json5::from_str(&src)
+        .wrap_err_with(|| format!("couldn't parse {}", args.input.display()))?;
+    let (name, author, rows) = match file {
+        LevelFile::Pack { name, author, rows } => (name, author, rows),
+        LevelFile::Main(rows) => {
+            ("Crop Circles".to_owned(), String::new(), rows)
+        }
+    };
+    let levels = rows.into_iter().flatten().collect::<Vec<_>>();
+    if levels.is_empty() {
+        bail!("{} has no levels in it", args.input.display());
+    }
+
+    let mut puzzles = Vec::new();
+    let mut answers = Vec::new();
+    for (idx, level) in levels.iter().enumerate() {
+        let solution = level.puzzle.solve();
+        // Ids are for finding levels in the files, not for players to read
+        let number = format!("{}.", idx + 1);
+        let stars = solution.as_ref().map(|it| stars(it.effort));
+        let title = match stars {
+            Some(stars) => format!(
+                "{} {}{}",
+                number,
+                "\u{2605}".repeat(stars),
+                "\u{2606}".repeat(5 - stars)
+            ),
+            None => number.clone(),
+        };
+        puzzles.push(Cell {
+            title,
+            drawing: Some(Drawing::new(&level.puzzle, &Board::default())),
+        });
+        answers.push(Cell {
+            title: number,
+            drawing: solution.map(|it| Drawing::new(&level.puzzle, &it.board)),
+        });
+        if answers.last().unwrap().drawing.is_none() {
+            eprintln!("no solution found for {} ({})", idx + 1, level.id);
+        }
+    }
+
+    let mut heading = name;
+    if !author.is_empty() {
+        write!(heading, " by {}", author).unwrap();
+    }
+    let mut pages = layout(&heading, &puzzles, args.per_page);
+    pages.extend(layout("Answers", &answers, args.per_page));
+
+    let stem = args.input.file_stem().unwrap_or_default().to_string_lossy();
+    match args.format {
+        Format::Html => {
+            let out =
+                args.out.unwrap_or_else(|| format!("{}.html", stem).into());
+            fs::write(&out, html(&heading, &pages)).wrap_err_with(|| {
+                format!("couldn't write {}", out.display())
+            })?;
+            println!("wrote {}", out.display());
+        }
+        Format::Svg => {
+            // One file per page, next to each other
+            let out = args.out.unwrap_or_else(|| stem.as_ref().into());
+            for (idx, page) in pages.iter().enumerate() {
+                let mut path = out.clone().into_os_string();
+                path.push(format!("-{}.svg", idx + 1));
+                let path = PathBuf::from(path);
+                fs::write(&path, page).wrap_err_with(|| {
+                    format!("couldn't write {}", path.display())
+                })?;
+                println!("wrote {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads everything after the program's name.
+fn parse_args(args: impl IntoIterator<Item = String>) -> eyre::Result<Args> {
+    const USAGE: &str = "usage: crop-circles-book <level file> [--per-page N] [--format html|svg] [--out PATH]";
+
+    let mut input = None;
+    let mut per_page = 6;
+    let mut format = Format::Html;
+    let mut out = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| eyre!("{}", USAGE));
+        match arg.as_str() {
+            "--per-page" => {
+                per_page =
+                    value()?.parse().ok().filter(|&n| n > 0).ok_or_else(
+                        || eyre!("--per-page needs a number above 0"),
+                    )?;
+            }
+            "--format" => {
+                format = match value()?.as_str() {
+                    "html" => Format::Html,
+                    "svg" => Format::Svg,
+                    other => {
+                        bail!("unknown format {:?}, try html or svg", other)
+                    }
+                };
+            }
+            "--out" => out = Some(value()?.into()),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if input.is_none() && !arg.starts_with("--") => {
+                input = Some(arg.into())
+            }
+            _ => bail!("unexpected argument {:?}\n{}", arg, USAGE),
+        }
+    }
+
+    Ok(Args {
+        input: input.ok_or_else(|| eyre!("{}", USAGE))?,
+        per_page,
+        format,
+        out,
+    })
+}
+
+/// How much searching it takes to earn each star past the first.
+///
+/// Going by the built-in levels, which take 10 to 500 or so, and the radius
+/// 2 dailies, which take 2,000 to 150,000.
+const STAR_EFFORT: [usize; 4] = [20, 300, 10_000, 40_000];
+
+/// More searching = harder puzzle, roughly.
+fn stars(effort: usize) -> usize {
+    1 + STAR_EFFORT.iter().filter(|&&it| effort >= it).count()
+}
+
+struct Cell {
+    title: String,
+    /// Missing if there's nothing to draw, like an answer the solver
+    /// couldn't find
+    drawing: Option<Drawing>,
+}
+
+/// Put the cells on as many A4 pages as they need, in a grid.
+fn layout(heading: &str, cells: &[Cell], per_page: usize) -> Vec<String> {
+    let cols = (per_page as f32).sqrt().ceil() as usize;
+    let rows = per_page.div_ceil(cols);
+    let cell_w = (PAGE_WIDTH - PAGE_MARGIN * 2.0 - GAP * (cols - 1) as f32)
+        / cols as f32;
+    let cell_h = (PAGE_HEIGHT
+        - PAGE_MARGIN * 2.0
+        - HEADER_HEIGHT
+        - GAP * (rows - 1) as f32)
+        / rows as f32;
+
+    cells
+        .chunks(per_page)
+        .map(|chunk| {
+            let mut page = String::new();
+            writeln!(
+                page,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}mm" height="{h}mm">"#,
+                w = PAGE_WIDTH,
+                h = PAGE_HEIGHT
+            )
+            .unwrap();
+            writeln!(
+                page,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="8" text-anchor="middle" fill="{}">{}</text>"#,
+                PAGE_WIDTH / 2.0,
+                PAGE_MARGIN + 6.0,
+                INK,
+                escape(heading)
+            )
+            .unwrap();
+
+            for (idx, cell) in chunk.iter().enumerate() {
+                let x = PAGE_MARGIN + (idx % cols) as f32 * (cell_w + GAP);
+                let y = PAGE_MARGIN
+                    + HEADER_HEIGHT
+                    + (idx / cols) as f32 * (cell_h + GAP);
+                writeln!(
+                    page,
+                    r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="5" fill="{}">{}</text>"#,
+                    x,
+                    y + 5.0,
+                    INK,
+                    escape(&cell.title)
+                )
+                .unwrap();
+                match &cell.drawing {
+                    Some(drawing) => page.push_str(&drawing.placed(
+                        x,
+                        y + TITLE_HEIGHT,
+                        cell_w,
+                        cell_h - TITLE_HEIGHT,
+                    )),
+                    None => writeln!(
+                        page,
+                        r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="4" fill="{}">No solution found</text>"#,
+                        x,
+                        y + TITLE_HEIGHT + 5.0,
+                        INK
+                    )
+                    .unwrap(),
+                }
+            }
+
+            page.push_str("</svg>\n");
+            page
+        })
+        .collect()
+}
+
+fn html(title: &str, pages: &[String]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n\
+         @page {{ size: A4; margin: 0; }}\n\
+         body {{ margin: 0; }}\n\
+         .page {{ width: 210mm; height: 297mm; page-break-after: always; }}\n\
+         .page svg {{ display: block; }}\n\
+         </style>\n</head>\n<body>",
+        escape(title)
+    )
+    .unwrap();
+    for page in pages {
+        writeln!(out, "<div class=\"page\">\n{}</div>", page).unwrap();
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> eyre::Result<Args> {
+        parse_args(line.split_whitespace().map(str::to_owned))
+    }
+
+    fn cells(count: usize) -> Vec<Cell> {
+        (0..count)
+            .map(|idx| Cell {
+                title: format!("{}.", idx + 1),
+                drawing: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stars_go_from_one_to_five() {
+        assert_eq!(stars(0), 1);
+        assert_eq!(stars(19), 1);
+        assert_eq!(stars(20), 2);
+        assert_eq!(stars(9_999), 3);
+        assert_eq!(stars(40_000), 5);
+        assert_eq!(stars(usize::MAX), 5);
+    }
+
+    #[test]
+    fn cells_are_paged() {
+        for (per_page, pages) in [(1, 7), (6, 2), (7, 1), (8, 1)] {
+            let laid_out = layout("Test", &cells(7), per_page);
+            assert_eq!(laid_out.len(), pages, "{} per page", per_page);
+            let on_pages = laid_out
+                .iter()
+                .map(|page| page.matches("No solution found").count())
+                .collect::<Vec<_>>();
+            assert_eq!(on_pages.iter().sum::<usize>(), 7);
+            assert!(on_pages.iter().all(|&n| n <= per_page));
+            assert!(laid_out.iter().all(|page| !page.contains("NaN")));
+        }
+        // The last on the page still goes on it
+        let last = &layout("Test", &cells(7), 7)[0];
+        assert!(last.contains(">7.<"));
+    }
+
+    #[test]
+    fn args_have_defaults() {
+        let args = args("pack.json5").unwrap();
+        assert_eq!(args.input, PathBuf::from("pack.json5"));
+        assert_eq!(args.per_page, 6);
+        assert!(args.format == Format::Html);
+        assert_eq!(args.out, None);
+    }
+
+    #[test]
+    fn args_can_be_in_any_order() {
+        let args =
+            args("--format svg --out book pack.json5 --per-page 1").unwrap();
+        assert_eq!(args.input, PathBuf::from("pack.json5"));
+        assert_eq!(args.per_page, 1);
+        assert!(args.format == Format::Svg);
+        assert_eq!(args.out, Some(PathBuf::from("book")));
+    }
+
+    #[test]
+    fn bad_args_are_refused() {
+        for line in [
+            "",
+            "a.json5 b.json5",
+            "a.json5 --per-page 0",
+            "a.json5 --per-page lots",
+            "a.json5 --format pdf",
+            "a.json5 --out",
+            "a.json5 --pages 2",
+        ] {
+            assert!(args(line).is_err(), "{:?}", line);
+        }
+    }
+}
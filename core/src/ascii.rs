@@ -0,0 +1,411 @@
+//! Puzzles and boards as ASCII art, for bug reports and poking at things in
+//! a terminal.
+//!
+//! ```text
+//!               2
+//!
+//!             2
+//!
+//!           o---o
+//!
+//! 2   1   .   o---o
+//!              \ /
+//!           #   o
+//!
+//!             1
+//! ```
+//!
+//! Cells are `.`, or `o` when a path goes through them, and dead cells are
+//! `#`. Paths between cells are `---`, `/` and `\`. The marks for each
+//! flank are written off its end, reading towards the board. Marks are single
+//! digits, which is plenty since a cell can't have more than 6 junctions.
+
+use std::{fmt, num::NonZeroU8};
+
+use ahash::{AHashMap, AHashSet};
+use hex2d::Coordinate;
+
+use crate::{
+    geom::{EdgePos, HexEdge},
+    puzzle::{Board, Puzzle, FLANKS},
+};
+
+const EDGES: [HexEdge; 3] = [HexEdge::XY, HexEdge::ZY, HexEdge::ZX];
+
+/// Draw the puzzle with the board's paths on it.
+pub fn to_ascii(puzzle: &Puzzle, board: &Board) -> String {
+    let mut grid = AHashMap::new();
+    let radius = puzzle.radius() as i32;
+
+    for coord in Coordinate::new(0, 0).range_iter(radius) {
+        let cell = if puzzle.is_cell_dead(coord) {
+            '#'
+        } else if board.get_junction_count(coord) != 0 {
+            'o'
+        } else {
+            '.'
+        };
+        grid.insert(position(coord), cell);
+
+        for edge in EDGES {
+            if board.get_raw_paths(coord).contains(edge) {
+                for pos in edge_span(coord, edge) {
+                    grid.insert(pos, edge_char(edge));
+                }
+            }
+        }
+    }
+
+    for (marks, flank) in puzzle.marks().iter().zip(FLANKS) {
+        for (i, markset) in marks.iter().enumerate() {
+            let anchor = puzzle.flank_anchor(flank, i);
+            // Nearest the board last, so they read outside-in
+            for (k, mark) in markset.iter().rev().enumerate() {
+                let coord =
+                    anchor - Coordinate::from(flank).scale(k as i32 + 1);
+                let digit = char::from_digit(mark.get() as u32, 10);
+                grid.insert(position(coord), digit.unwrap_or('?'));
+            }
+        }
+    }
+
+    let min_row = grid.keys().map(|&(row, _)| row).min().unwrap_or(0);
+    let max_row = grid.keys().map(|&(row, _)| row).max().unwrap_or(0);
+    let min_col = grid.keys().map(|&(_, col)| col).min().unwrap_or(0);
+    let max_col = grid.keys().map(|&(_, col)| col).max().unwrap_or(0);
+    let mut out = String::new();
+    for row in min_row..=max_row {
+        let line = (min_col..=max_col)
+            .map(|col| grid.get(&(row, col)).copied().unwrap_or(' '))
+            .collect::<String>();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Read back something drawn by [`to_ascii`]. Any amount of blank space
+/// around the drawing is fine.
+pub fn from_ascii(text: &str) -> Result<(Puzzle, Board), ParseError> {
+    let lines = text
+        .lines()
+        .map(|line| line.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let at = |(row, col): (usize, usize)| {
+        lines.get(row).and_then(|line| line.get(col)).copied()
+    };
+    let is_cell = |c: char| matches!(c, '.' | 'o' | '#');
+
+    let cell_rows = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.iter().copied().any(is_cell))
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    let Some(&first_row) = cell_rows.first() else {
+        return Err(ParseError::new((0, 0), "there aren't any cells"));
+    };
+    if cell_rows.len() % 2 == 0 {
+        return Err(ParseError::new(
+            (first_row, 0),
+            "a hexagon needs an odd number of rows of cells",
+        ));
+    }
+    let radius = cell_rows.len() as i32 / 2;
+    // The middle row goes all the way across
+    let middle = first_row + radius as usize * 2;
+    let middle_cols = lines
+        .get(middle)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|&(_, &c)| is_cell(c))
+        .map(|(col, _)| col as i32)
+        .collect::<Vec<_>>();
+    let center_col = match middle_cols.as_slice() {
+        [first, .., last] => (first + last) / 2,
+        [only] => *only,
+        [] => {
+            return Err(ParseError::new(
+                (middle, 0),
+                "expected the middle row of cells here",
+            ))
+        }
+    };
+
+    // Where a spot relative to the middle cell is in the text, if it's there
+    // at all
+    let locate = |(row, col): (i32, i32)| {
+        let (row, col) = (middle as i32 + row, center_col + col);
+        (row >= 0 && col >= 0).then_some((row as usize, col as usize))
+    };
+    let mut used = AHashSet::new();
+
+    let mut dead_cells = AHashSet::new();
+    for coord in Coordinate::new(0, 0).range_iter(radius) {
+        let pos = locate(position(coord));
+        match pos.and_then(at) {
+            Some('#') => {
+                dead_cells.insert(coord);
+            }
+            Some(c) if is_cell(c) => {}
+            _ => {
+                return Err(ParseError::new(
+                    pos.unwrap_or_default(),
+                    "expected a cell here to make a hexagon",
+                ))
+            }
+        }
+        used.insert(pos.unwrap());
+    }
+
+    // Just to find where the marks go
+    let outline =
+        Puzzle::new(radius as u32, Default::default(), AHashSet::new());
+    let mut marks = [(); 3].map(|_| Vec::new());
+    for (marks, flank) in marks.iter_mut().zip(FLANKS) {
+        for i in 0..=radius as usize * 2 {
+            let anchor = outline.flank_anchor(flank, i);
+            let mut markset = Vec::new();
+            for k in 1.. {
+                let coord = anchor - Coordinate::from(flank).scale(k);
+                let Some(pos) = locate(position(coord)) else {
+                    break;
+                };
+                let Some(mark) = at(pos)
+                    .and_then(|c| c.to_digit(10))
+                    .and_then(|d| NonZeroU8::new(d as u8))
+                else {
+                    break;
+                };
+                markset.push(mark);
+                used.insert(pos);
+            }
+            markset.reverse();
+            marks.push(markset);
+        }
+    }
+
+    let puzzle = Puzzle::new(radius as u32, marks, dead_cells);
+    let mut board = Board::default();
+    for coord in Coordinate::new(0, 0).range_iter(radius) {
+        for edge in EDGES {
+            let span = edge_span(coord, edge)
+                .into_iter()
+                .filter_map(locate)
+                .collect::<Vec<_>>();
+            let drawn =
+                span.iter().filter(|&&pos| at(pos) == Some(edge_char(edge)));
+            match drawn.count() {
+                0 => continue,
+                n if n == span.len() => {}
+                _ => {
+                    return Err(ParseError::new(
+                        span[0],
+                        "path is only partly drawn",
+                    ))
+                }
+            }
+            let edge_pos = EdgePos::new_raw(coord, edge);
+            if !board.can_twiddle_path(&puzzle, edge_pos) {
+                return Err(ParseError::new(
+                    span[0],
+                    "paths can only go between live cells",
+                ));
+            }
+            board.set_path(&puzzle, edge_pos, true);
+            used.extend(span);
+        }
+    }
+
+    for (row, line) in lines.iter().enumerate() {
+        for (col, c) in line.iter().enumerate() {
+            if !c.is_whitespace() && !used.contains(&(row, col)) {
+                return Err(ParseError::new(
+                    (row, col),
+                    format!("didn't expect {:?} here", c),
+                ));
+            }
+        }
+    }
+
+    Ok((puzzle, board))
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new((row, col): (usize, usize), message: impl Into<String>) -> Self {
+        Self {
+            line: row + 1,
+            column: col + 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Row and column relative to the middle cell. Rows of cells have a row of
+/// diagonal paths between them, and cells in a row are 4 apart to leave room
+/// for `---`.
+fn position(coord: Coordinate) -> (i32, i32) {
+    let z = -coord.x - coord.y;
+    (2 * z, 2 * (2 * coord.x + z))
+}
+
+/// Where the path from the cell along the edge gets drawn
+fn edge_span(coord: Coordinate, edge: HexEdge) -> Vec<(i32, i32)> {
+    let (row, col) = position(coord);
+    match edge {
+        HexEdge::XY => vec![(row, col + 1), (row, col + 2), (row, col + 3)],
+        HexEdge::ZY => vec![(row + 1, col + 1)],
+        HexEdge::ZX => vec![(row + 1, col - 1)],
+    }
+}
+
+fn edge_char(edge: HexEdge) -> char {
+    match edge {
+        HexEdge::XY => '-',
+        HexEdge::ZY => '\\',
+        HexEdge::ZX => '/',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generate, Recipe};
+
+    /// Starts with a newline to keep the drawing lined up in here
+    const EXAMPLE: &str = "
+              2
+
+            2
+
+          o---o
+
+2   1   .   o---o
+             \\ /
+          #   o
+
+            1
+";
+
+    /// Every path on the board, in a form that can be compared.
+    fn paths(puzzle: &Puzzle, board: &Board) -> Vec<(Coordinate, HexEdge)> {
+        Coordinate::new(0, 0)
+            .range_iter(puzzle.radius() as i32)
+            .flat_map(|coord| {
+                EDGES
+                    .into_iter()
+                    .filter(move |&edge| {
+                        board.get_raw_paths(coord).contains(edge)
+                    })
+                    .map(move |edge| (coord, edge))
+            })
+            .collect()
+    }
+
+    fn error(text: &str) -> ParseError {
+        from_ascii(text).map(|_| ()).unwrap_err()
+    }
+
+    #[test]
+    fn reads_back_what_it_draws() {
+        let recipe = Recipe {
+            radius: 2,
+            dead_cells: 2,
+            coverage: 0.6,
+            hidden: 0.3,
+        };
+        for seed in 0..20 {
+            let (puzzle, board) = generate(seed, &recipe);
+            let (puzzle2, board2) =
+                from_ascii(&to_ascii(&puzzle, &board)).unwrap();
+            assert_eq!(puzzle2.shape(), puzzle.shape(), "seed {}", seed);
+            assert_eq!(puzzle2.marks(), puzzle.marks(), "seed {}", seed);
+            assert_eq!(
+                paths(&puzzle2, &board2),
+                paths(&puzzle, &board),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn draws_back_what_it_reads() {
+        let (puzzle, board) = from_ascii(EXAMPLE).unwrap();
+        assert_eq!(to_ascii(&puzzle, &board), &EXAMPLE[1..]);
+    }
+
+    #[test]
+    fn wants_an_odd_number_of_rows() {
+        let err = error(
+            "
+  .   .
+
+.   .   .
+",
+        );
+        assert!(err.message.contains("odd number of rows"), "{}", err);
+    }
+
+    #[test]
+    fn no_paths_into_dead_cells() {
+        let err = error(
+            "
+  .   .
+
+.   #---.
+
+  .   .
+",
+        );
+        assert_eq!((err.line, err.column), (4, 6));
+        assert!(err.message.contains("live cells"), "{}", err);
+    }
+
+    #[test]
+    fn no_stray_characters() {
+        let err = error(
+            "
+  .   .
+
+.   .   .  x
+
+  .   .
+",
+        );
+        assert_eq!((err.line, err.column), (4, 12));
+    }
+
+    #[test]
+    fn no_half_drawn_paths() {
+        let err = error(
+            "
+  .   .
+
+.   .-- .
+
+  .   .
+",
+        );
+        assert_eq!((err.line, err.column), (4, 6));
+        assert!(err.message.contains("partly drawn"), "{}", err);
+    }
+}
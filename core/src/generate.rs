@@ -0,0 +1,338 @@
+//! Making up puzzles from a seed. The same seed makes the same puzzle on
+//! every machine, which is what the daily puzzle relies on.
+
+use std::num::NonZeroU8;
+
+use ahash::AHashSet;
+use hex2d::{Coordinate, Direction};
+
+use crate::{
+    geom::EdgePos,
+    puzzle::{Board, Level, Par, Puzzle, FLANKS},
+};
+
+/// Tries at a good path before settling for the best one so far
+const ATTEMPTS: usize = 64;
+
+/// What sort of puzzle to make.
+#[derive(Debug, Clone, Copy)]
+pub struct Recipe {
+    pub radius: u32,
+    pub dead_cells: usize,
+    /// How much of the field the path should try to go over, from 0 to 1
+    pub coverage: f32,
+    /// Chance of leaving each line of marks off. Fewer marks means more
+    /// ways to solve it, so this makes it easier.
+    pub hidden: f32,
+}
+
+/// Make a puzzle, along with the board it was made from, which solves it.
+pub fn generate(seed: u64, recipe: &Recipe) -> (Puzzle, Board) {
+    let mut rng = Rng(seed);
+    let radius = recipe.radius as i32;
+    let cells = Coordinate::new(0, 0).range_iter(radius).collect::<Vec<_>>();
+
+    let mut best: Option<(usize, Puzzle, Board)> = None;
+    for _ in 0..ATTEMPTS {
+        let mut dead_cells = AHashSet::new();
+        while dead_cells.len() < recipe.dead_cells.min(cells.len() - 1) {
+            dead_cells.insert(cells[rng.below(cells.len())]);
+        }
+        let shape = Puzzle::new(recipe.radius, Default::default(), dead_cells);
+        let board = random_path(&mut rng, &shape, &cells, recipe.coverage);
+
+        let (marks, shown) = marks_for(&mut rng, &shape, &board, recipe.hidden);
+        let puzzle = Puzzle::new(recipe.radius, marks, shape.dead_cells());
+        if board.is_solved(&puzzle).is_err() {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(most, ..)| shown > *most) {
+            best = Some((shown, puzzle, board));
+        }
+    }
+
+    match best {
+        Some((_, puzzle, board)) => (puzzle, board),
+        // Nothing ever checked out, so hand back a puzzle that anything
+        // solves
+        None => (
+            Puzzle::new(recipe.radius, Default::default(), AHashSet::new()),
+            Board::default(),
+        ),
+    }
+}
+
+/// Wander from a random cell without going over any path twice, so it's
+/// always a single pass.
+fn random_path(
+    rng: &mut Rng,
+    shape: &Puzzle,
+    cells: &[Coordinate],
+    coverage: f32,
+) -> Board {
+    let live = cells
+        .iter()
+        .copied()
+        .filter(|&c| shape.is_valid(c))
+        .collect::<Vec<_>>();
+    let target = ((live.len() as f32 * coverage).round() as usize).max(2);
+
+    let mut board = Board::default();
+    let mut here = live[rng.below(live.len())];
+    let mut visited = AHashSet::from_iter([here]);
+    while visited.len() < target {
+        let options = Direction::all()
+            .iter()
+            .map(|&dir| EdgePos::new(here, dir))
+            .filter(|&edge| {
+                board.can_twiddle_path(shape, edge)
+                    && board.get_path(edge) != Some(true)
+            })
+            .collect::<Vec<_>>();
+        if options.is_empty() {
+            break;
+        }
+        // Prefer going somewhere new, so the path spreads out
+        let fresh = options
+            .iter()
+            .copied()
+            .filter(|edge| !visited.contains(&other_end(*edge, here)))
+            .collect::<Vec<_>>();
+        let pool = if fresh.is_empty() || rng.below(4) == 0 {
+            &options
+        } else {
+            &fresh
+        };
+        let edge = pool[rng.below(pool.len())];
+        board.set_path(shape, edge, true);
+        here = other_end(edge, here);
+        visited.insert(here);
+    }
+    board
+}
+
+fn other_end(edge: EdgePos, from: Coordinate) -> Coordinate {
+    let [a, b] = edge.spans();
+    if a == from {
+        b
+    } else {
+        a
+    }
+}
+
+/// Read the marks off the board the same way the checker scans them, and
+/// return how many lines of them there are.
+fn marks_for(
+    rng: &mut Rng,
+    shape: &Puzzle,
+    board: &Board,
+    hidden: f32,
+) -> ([Vec<Vec<NonZeroU8>>; 3], usize) {
+    let radius = shape.radius() as i32;
+    let mut shown = 0;
+    let marks = FLANKS.map(|flank| {
+        (0..=radius as usize * 2)
+            .map(|i| {
+                let anchor = shape.flank_anchor(flank, i);
+                let length = radius * 2 - (i as i32 - radius).abs();
+                let found = (0..=length)
+                    .filter_map(|j| {
+                        let coord = anchor + Coordinate::from(flank).scale(j);
+                        NonZeroU8::new(board.get_junction_count(coord))
+                    })
+                    .collect::<Vec<_>>();
+                if found.is_empty() || rng.chance(hidden) {
+                    return Vec::new();
+                }
+                shown += 1;
+                found
+            })
+            .collect()
+    });
+    (marks, shown)
+}
+
+/// How each day of the week goes, starting on Monday. It gets harder
+/// towards the weekend. Any bigger than radius 2 and the marks run off the
+/// top of the screen.
+const WEEK: [Recipe; 7] = [
+    Recipe {
+        radius: 1,
+        dead_cells: 0,
+        coverage: 1.0,
+        hidden: 0.25,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 0,
+        coverage: 0.6,
+        hidden: 0.5,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 1,
+        coverage: 0.7,
+        hidden: 0.4,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 1,
+        coverage: 0.8,
+        hidden: 0.3,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 2,
+        coverage: 0.8,
+        hidden: 0.2,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 2,
+        coverage: 0.9,
+        hidden: 0.1,
+    },
+    Recipe {
+        radius: 2,
+        dead_cells: 3,
+        coverage: 1.0,
+        hidden: 0.0,
+    },
+];
+
+/// The puzzle for a day, counted in days since 1970-01-01 UTC.
+///
+/// Par is however many paths the puzzle was made with.
+pub fn daily(day: i64) -> Level {
+    let (puzzle, board) = generate(day as u64, &WEEK[weekday(day)]);
+    let moves = Coordinate::new(0, 0)
+        .range_iter(puzzle.radius() as i32)
+        .map(|c| board.get_raw_paths(c).len() as u32)
+        .sum();
+    Level {
+        id: format!("daily-{}", iso_date(day)),
+        puzzle,
+        par: Some(Par {
+            moves: Some(moves),
+            seconds: None,
+        }),
+    }
+}
+
+/// 0 for Monday through 6 for Sunday.
+pub fn weekday(day: i64) -> usize {
+    // 1970-01-01 was a Thursday
+    (day + 3).rem_euclid(7) as usize
+}
+
+/// Like `2023-01-07`.
+pub fn iso_date(day: i64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// splitmix64. Nothing fancy, but it comes out the same everywhere.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, p: f32) -> bool {
+        let roll = (self.next() >> 40) as f32 / (1u64 << 24) as f32;
+        roll < p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::to_ascii;
+
+    /// If this changes, everyone's daily history changes with it.
+    #[test]
+    fn dailies_stay_the_same() {
+        let level = daily(19_364);
+        assert_eq!(level.id, "daily-2023-01-07");
+        assert_eq!(level.par.unwrap().moves, Some(15));
+        assert_eq!(
+            to_ascii(&level.puzzle, &Board::default()),
+            "
+                                      2
+
+                                1   2
+
+                              2   2
+
+                        2   2   2   2
+
+                      2   4   2   2
+
+            1   2   #   .   .   2
+
+  2   2   2   2   .   .   .   .   1
+
+2   2   2   2   .   .   .   .   .
+
+          4   2   .   .   #   .   2
+
+        2   2   1   .   .   .   1   2
+
+                      2   2   2   2
+
+                        4   2   2   2
+
+                          2   2   1
+
+                                2
+"[1..]
+        );
+    }
+
+    #[test]
+    fn generated_boards_solve_their_puzzles() {
+        for (seed, recipe) in WEEK.iter().enumerate() {
+            let (puzzle, board) = generate(seed as u64, recipe);
+            assert!(board.is_solved(&puzzle).is_ok(), "{:?}", recipe);
+        }
+    }
+
+    #[test]
+    fn iso_dates() {
+        assert_eq!(iso_date(0), "1970-01-01");
+        assert_eq!(iso_date(-1), "1969-12-31");
+        assert_eq!(iso_date(11_016), "2000-02-29");
+        assert_eq!(iso_date(11_017), "2000-03-01");
+        assert_eq!(iso_date(19_782), "2024-02-29");
+        // Not a leap year, being a century
+        assert_eq!(iso_date(47_540), "2100-02-28");
+        assert_eq!(iso_date(47_541), "2100-03-01");
+        assert_eq!(iso_date(-25_508), "1900-03-01");
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(weekday(0), 3);
+        assert_eq!(weekday(-1), 2);
+        assert_eq!(weekday(19_364), 5);
+        assert_eq!(weekday(20_745), 0);
+    }
+}
@@ -17,7 +17,7 @@ pub enum HexEdge {
 }
 
 impl HexEdge {
-    pub fn to_hex2d(&self) -> Direction {
+    pub fn to_hex2d(self) -> Direction {
         match self {
             HexEdge::XY => Direction::XY,
             HexEdge::ZY => Direction::ZY,
@@ -0,0 +1,10 @@
+//! The rules of Crop Circles: the hex grid, puzzles, boards, and checking
+//! whether a board solves a puzzle.
+//!
+//! Nothing in here knows about macroquad.
+
+pub mod ascii;
+pub mod generate;
+pub mod geom;
+pub mod puzzle;
+pub mod svg;
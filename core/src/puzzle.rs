@@ -0,0 +1,218 @@
+mod checker;
+mod solver;
+
+use std::{collections::hash_map, num::NonZeroU8};
+
+use ahash::{AHashMap, AHashSet};
+use hex2d::{Angle, Coordinate, Direction};
+use serde::{Deserialize, Serialize};
+
+use crate::geom::{EdgePos, EdgeSet};
+
+pub use checker::FailureReason;
+pub use solver::Solution;
+
+/// Which way each of `Puzzle::marks` is along, in the same order.
+pub const FLANKS: [Direction; 3] =
+    [Direction::XY, Direction::YZ, Direction::ZX];
+
+#[derive(Debug, Deserialize)]
+pub struct Puzzle {
+    radius: u32,
+    marks: [Vec<Vec<NonZeroU8>>; 3],
+    #[serde(default)]
+    dead_cells: AHashSet<Coordinate>,
+}
+
+impl Puzzle {
+    pub fn new(
+        radius: u32,
+        marks: [Vec<Vec<NonZeroU8>>; 3],
+        dead_cells: AHashSet<Coordinate>,
+    ) -> Self {
+        Self {
+            radius,
+            marks,
+            dead_cells,
+        }
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn marks(&self) -> [&Vec<Vec<NonZeroU8>>; 3] {
+        self.marks.each_ref()
+    }
+
+    pub fn dead_cells(&self) -> AHashSet<Coordinate> {
+        self.dead_cells.clone()
+    }
+
+    pub fn is_cell_dead(&self, coord: Coordinate) -> bool {
+        self.dead_cells.contains(&coord)
+    }
+
+    /// can the alien go over there
+    pub fn is_valid(&self, coord: Coordinate) -> bool {
+        coord.distance(Coordinate::new(0, 0)) <= self.radius as i32
+            && !self.dead_cells.contains(&coord)
+    }
+
+    /// The cell on the rim that the `idx`th set of marks along a flank sits
+    /// next to.
+    pub fn flank_anchor(&self, flank: Direction, idx: usize) -> Coordinate {
+        let radius = self.radius as i32;
+        // ... -2, -1, 0, 1, 2 ...
+        let centered_idx = idx as i32 - radius;
+        let side_center =
+            Coordinate::new(0, 0) - Coordinate::from(flank).scale(radius);
+        let turn = if centered_idx > 0 {
+            Angle::Right
+        } else {
+            Angle::Left
+        };
+        let offset = Coordinate::from(flank + turn).scale(centered_idx.abs());
+        side_center + offset
+    }
+
+    pub fn shape(&self) -> PuzzleShape {
+        PuzzleShape {
+            radius: self.radius,
+            dead_cells: self.dead_cells.clone(),
+        }
+    }
+}
+
+/// Which cells a puzzle has, without its marks. A board drawn on one puzzle
+/// still makes sense on another with the same shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleShape {
+    radius: u32,
+    dead_cells: AHashSet<Coordinate>,
+}
+
+impl PuzzleShape {
+    pub fn fits(&self, puzzle: &Puzzle) -> bool {
+        self.radius == puzzle.radius && self.dead_cells == puzzle.dead_cells
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Level {
+    /// Used to look up this level's text in the locale files
+    pub id: String,
+    pub puzzle: Puzzle,
+    /// What a good solve looks like, if the designer set one
+    #[serde(default)]
+    pub par: Option<Par>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Par {
+    /// Path toggles
+    #[serde(default)]
+    pub moves: Option<u32>,
+    #[serde(default)]
+    pub seconds: Option<u32>,
+}
+
+impl Par {
+    /// Was a solve this quick and this tidy? Anything left unset counts as
+    /// met.
+    pub fn is_met(&self, seconds: f32, moves: u32) -> bool {
+        self.moves.is_none_or(|par| moves <= par)
+            && self.seconds.is_none_or(|par| seconds <= par as f32)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(
+    from = "Vec<(Coordinate, EdgeSet)>",
+    into = "Vec<(Coordinate, EdgeSet)>"
+)]
+pub struct Board {
+    paths: AHashMap<Coordinate, EdgeSet>,
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Self {
+            paths: AHashMap::new(),
+        }
+    }
+
+    pub fn can_twiddle_path(&self, puzzle: &Puzzle, edge: EdgePos) -> bool {
+        let [a, b] = edge.spans();
+        puzzle.is_valid(a) && puzzle.is_valid(b)
+    }
+
+    /// Get whether the path is there, missing, or invalid
+    pub fn get_path(&self, edge: EdgePos) -> Option<bool> {
+        Some(self.paths.get(&edge.coord)?.contains(edge.edge))
+    }
+
+    pub fn get_junction_count(&self, coord: Coordinate) -> u8 {
+        let count = Direction::all()
+            .iter()
+            .filter(|dir| {
+                let edge = EdgePos::new(coord, **dir);
+                self.get_path(edge) == Some(true)
+            })
+            .count();
+        count as u8
+    }
+
+    /// Return the old value of the path
+    pub fn set_path(
+        &mut self,
+        puzzle: &Puzzle,
+        edge: EdgePos,
+        newval: bool,
+    ) -> Option<bool> {
+        if !self.can_twiddle_path(puzzle, edge) {
+            None
+        } else if newval {
+            let here = self.paths.entry(edge.coord).or_default();
+            let prev = here.contains(edge.edge);
+            here.insert(edge.edge);
+            Some(prev)
+        } else if let hash_map::Entry::Occupied(mut entry) =
+            self.paths.entry(edge.coord)
+        {
+            let here = entry.get_mut();
+            let prev = here.contains(edge.edge);
+            here.remove(edge.edge);
+            if here.is_empty() {
+                entry.remove_entry();
+            }
+            Some(prev)
+        } else {
+            // as an optimization don't bother adding and then immediately
+            // removing when removing. there was nothing here.
+            Some(false)
+        }
+    }
+
+    pub fn get_raw_paths(&self, coord: Coordinate) -> EdgeSet {
+        self.paths.get(&coord).copied().unwrap_or_default()
+    }
+}
+
+// Coordinates can't be map keys in json, so store the paths as a list
+impl From<Vec<(Coordinate, EdgeSet)>> for Board {
+    fn from(paths: Vec<(Coordinate, EdgeSet)>) -> Self {
+        Self {
+            paths: paths
+                .into_iter()
+                .filter(|(_, edges)| !edges.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl From<Board> for Vec<(Coordinate, EdgeSet)> {
+    fn from(board: Board) -> Self {
+        board.paths.into_iter().collect()
+    }
+}
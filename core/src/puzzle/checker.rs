@@ -8,8 +8,8 @@ use super::{Board, Puzzle};
 
 impl Board {
     pub fn is_solved(&self, puzzle: &Puzzle) -> Result<(), FailureReason> {
-        let Some(euler) = self.find_euler_path() else {
-            return Err(FailureReason::NotSinglePass)
+        let Some(_euler) = self.find_euler_path() else {
+            return Err(FailureReason::NotSinglePass);
         };
         // println!("{:?}", euler);
 
@@ -59,7 +59,7 @@ impl Board {
 
                     popcnt += 1;
 
-                    if j as usize >= markset.len() {
+                    if scanner as usize >= markset.len() {
                         // there's more junctions here than the plan called for
                         //                 println!(
                         //                     "failed at {:?} idx {},{}, ran out",
@@ -184,3 +184,40 @@ fn make_graph(board: &Board) -> AHashMap<Coordinate, u8> {
 
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::from_ascii;
+
+    #[test]
+    fn marks_count_junctions_not_cells() {
+        // The only junction on the marked line is in its second cell
+        let (puzzle, board) = from_ascii(
+            "
+1   .   o
+         \\
+  .   .   o
+
+    .   .
+",
+        )
+        .unwrap();
+        assert_eq!(board.is_solved(&puzzle), Ok(()));
+    }
+
+    #[test]
+    fn marks_still_have_to_match() {
+        let (puzzle, board) = from_ascii(
+            "
+2   .   o
+         \\
+  .   .   o
+
+    .   .
+",
+        )
+        .unwrap();
+        assert_eq!(board.is_solved(&puzzle), Err(FailureReason::CountFailed));
+    }
+}
@@ -0,0 +1,253 @@
+use std::num::NonZeroU8;
+
+use ahash::{AHashMap, AHashSet};
+use hex2d::Coordinate;
+
+use crate::geom::{EdgePos, HexEdge};
+
+use super::{Board, Puzzle, FLANKS};
+
+/// How many partial boards to look at before giving up
+const SEARCH_LIMIT: usize = 2_000_000;
+
+pub struct Solution {
+    pub board: Board,
+    /// How many partial boards got looked at on the way. A rough measure of
+    /// how hard the puzzle is.
+    pub effort: usize,
+}
+
+impl Puzzle {
+    /// Find a board that solves this, by trying every single pass through
+    /// the field.
+    ///
+    /// Returns `None` if there isn't one, or if the puzzle's too big to
+    /// find one in reasonable time.
+    pub fn solve(&self) -> Option<Solution> {
+        let mut search = Search::new(self);
+        for start in 0..search.cells.len() {
+            if search.walk(start) {
+                return Some(Solution {
+                    board: search.board(),
+                    effort: search.seen.len(),
+                });
+            }
+            if search.gave_up {
+                break;
+            }
+        }
+        None
+    }
+}
+
+/// Cell indices along a flank, in order
+type Line = Vec<usize>;
+
+struct Search<'a> {
+    puzzle: &'a Puzzle,
+    cells: Vec<Coordinate>,
+    edges: Vec<EdgePos>,
+    /// For each cell, the edges out of it and the cell at the other end
+    adjacent: Vec<Vec<(usize, usize)>>,
+    /// Every flank that has marks
+    lines: Vec<(Line, &'a [NonZeroU8])>,
+
+    /// Junction count of each cell
+    degrees: Vec<u8>,
+    /// Bitset of which edges are drawn
+    used: Vec<u64>,
+    /// Drawn edges and where the pass ends, for every partial board tried
+    seen: AHashSet<(Vec<u64>, usize)>,
+    gave_up: bool,
+}
+
+impl<'a> Search<'a> {
+    fn new(puzzle: &'a Puzzle) -> Self {
+        let radius = puzzle.radius as i32;
+        let cells = Coordinate::new(0, 0)
+            .range_iter(radius)
+            .filter(|&c| puzzle.is_valid(c))
+            .collect::<Vec<_>>();
+        let index = cells
+            .iter()
+            .enumerate()
+            .map(|(idx, &c)| (c, idx))
+            .collect::<AHashMap<_, _>>();
+
+        let mut edges = Vec::new();
+        let mut adjacent = vec![Vec::new(); cells.len()];
+        for (here, &coord) in cells.iter().enumerate() {
+            for edge in [HexEdge::XY, HexEdge::ZY, HexEdge::ZX] {
+                let Some(&there) = index.get(&(coord + edge.to_hex2d())) else {
+                    continue;
+                };
+                adjacent[here].push((edges.len(), there));
+                adjacent[there].push((edges.len(), here));
+                edges.push(EdgePos::new_raw(coord, edge));
+            }
+        }
+
+        // Same scan as the checker does
+        let mut lines = Vec::new();
+        for (marks, flank) in puzzle.marks.iter().zip(FLANKS) {
+            for (i, markset) in marks.iter().enumerate() {
+                if markset.is_empty() {
+                    continue;
+                }
+                let anchor = puzzle.flank_anchor(flank, i);
+                let length = radius * 2 - (i as i32 - radius).abs();
+                let line = (0..=length)
+                    .filter_map(|j| {
+                        let coord = anchor + Coordinate::from(flank).scale(j);
+                        index.get(&coord).copied()
+                    })
+                    .collect();
+                lines.push((line, markset.as_slice()));
+            }
+        }
+
+        Self {
+            puzzle,
+            degrees: vec![0; cells.len()],
+            used: vec![0; edges.len() / 64 + 1],
+            cells,
+            edges,
+            adjacent,
+            lines,
+            seen: AHashSet::new(),
+            gave_up: false,
+        }
+    }
+
+    /// Carry on the pass from `here`. Returns whether it found a solution,
+    /// leaving it drawn if so.
+    fn walk(&mut self, here: usize) -> bool {
+        if self.seen.len() >= SEARCH_LIMIT {
+            self.gave_up = true;
+            return false;
+        }
+        if !self.seen.insert((self.used.clone(), here)) {
+            return false;
+        }
+        if self.marks_match() && self.board().is_solved(self.puzzle).is_ok() {
+            return true;
+        }
+
+        for k in 0..self.adjacent[here].len() {
+            let (edge, there) = self.adjacent[here][k];
+            let (word, bit) = (edge / 64, 1 << (edge % 64));
+            if self.used[word] & bit != 0 {
+                continue;
+            }
+
+            self.used[word] |= bit;
+            self.degrees[here] += 1;
+            self.degrees[there] += 1;
+            if self.could_match() && self.walk(there) {
+                return true;
+            }
+            self.used[word] &= !bit;
+            self.degrees[here] -= 1;
+            self.degrees[there] -= 1;
+
+            if self.gave_up {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Junctions never go away as the pass goes on, so rule out boards
+    /// that already have too many, or ones too big, along any flank.
+    fn could_match(&self) -> bool {
+        self.lines.iter().all(|(line, marks)| {
+            let biggest = marks.iter().max().map_or(0, |m| m.get());
+            let mut count = 0;
+            for &cell in line {
+                let degree = self.degrees[cell];
+                if degree == 0 {
+                    continue;
+                }
+                count += 1;
+                if degree > biggest || count > marks.len() {
+                    return false;
+                }
+            }
+            true
+        })
+    }
+
+    /// Is every flank exactly as marked?
+    fn marks_match(&self) -> bool {
+        self.lines.iter().all(|(line, marks)| {
+            let found = line
+                .iter()
+                .map(|&cell| self.degrees[cell])
+                .filter(|&degree| degree != 0);
+            found.eq(marks.iter().map(|m| m.get()))
+        })
+    }
+
+    fn board(&self) -> Board {
+        let mut board = Board::default();
+        for (idx, &edge) in self.edges.iter().enumerate() {
+            if self.used[idx / 64] & (1 << (idx % 64)) != 0 {
+                board.set_path(self.puzzle, edge, true);
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::*;
+    use crate::generate::{generate, Recipe};
+
+    fn marks(lines: &[&[u8]]) -> Vec<Vec<NonZeroU8>> {
+        lines
+            .iter()
+            .map(|line| {
+                line.iter().map(|&m| NonZeroU8::new(m).unwrap()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn solutions_are_solved() {
+        for hidden in [0.0, 0.5] {
+            let recipe = Recipe {
+                radius: 2,
+                dead_cells: 2,
+                coverage: 0.6,
+                hidden,
+            };
+            for seed in 0..10 {
+                let (puzzle, _) = generate(seed, &recipe);
+                let solution = puzzle.solve().unwrap();
+                assert!(
+                    solution.board.is_solved(&puzzle).is_ok(),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_solution_to_impossible_puzzles() {
+        // More marks than there are cells in the line
+        let puzzle = Puzzle::new(
+            1,
+            [
+                marks(&[&[1, 1, 1], &[], &[]]),
+                marks(&[&[], &[], &[]]),
+                marks(&[&[], &[], &[]]),
+            ],
+            AHashSet::new(),
+        );
+        assert!(puzzle.solve().is_none());
+    }
+}
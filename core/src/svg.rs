@@ -0,0 +1,257 @@
+//! Draw a puzzle and a board as an SVG, for design reviews and printing.
+
+use std::fmt::Write;
+
+use hex2d::Coordinate;
+
+use crate::{
+    geom::HexEdge,
+    puzzle::{Board, Puzzle, FLANKS},
+};
+
+/// Center to corner of each hex, in SVG units
+const HEX_SIZE: f32 = 20.0;
+const SQRT_3: f32 = 1.732_050_8;
+
+const FIELD_FILL: &str = "#f2e2a0";
+const FIELD_STROKE: &str = "#8bb392";
+const DEAD_FILL: &str = "#a3b3a2";
+const PATH_STROKE: &str = "#cc2d47";
+const MARK_FILL: &str = "#42756b";
+
+/// Render the puzzle with the board's paths drawn on it.
+///
+/// Pass an empty board to get a blank puzzle to print out.
+pub fn to_svg(puzzle: &Puzzle, board: &Board) -> String {
+    Drawing::new(puzzle, board).to_svg()
+}
+
+/// A rendered puzzle, to put in a standalone file or lay out on a page.
+pub struct Drawing {
+    /// x, y, width, height
+    view_box: [f32; 4],
+    body: String,
+}
+
+impl Drawing {
+    pub fn new(puzzle: &Puzzle, board: &Board) -> Self {
+        let mut body = String::new();
+        let mut bounds = Bounds::default();
+        let radius = puzzle.radius() as i32;
+        let cells =
+            Coordinate::new(0, 0).range_iter(radius).collect::<Vec<_>>();
+
+        for &coord in &cells {
+            let (cx, cy) = center(coord);
+            let points = (0..6)
+                .map(|i| {
+                    // Pointy side up
+                    let angle = (60.0 * i as f32 - 30.0).to_radians();
+                    let (x, y) = (
+                        cx + HEX_SIZE * angle.cos(),
+                        cy + HEX_SIZE * angle.sin(),
+                    );
+                    bounds.add(x, y);
+                    format!("{:.2},{:.2}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let fill = if puzzle.is_cell_dead(coord) {
+                DEAD_FILL
+            } else {
+                FIELD_FILL
+            };
+            writeln!(
+            body,
+            r#"  <polygon points="{}" fill="{}" stroke="{}" stroke-width="1"/>"#,
+            points, fill, FIELD_STROKE
+        )
+        .unwrap();
+        }
+
+        for &coord in &cells {
+            let (x1, y1) = center(coord);
+            for edge in [HexEdge::XY, HexEdge::ZY, HexEdge::ZX] {
+                if !board.get_raw_paths(coord).contains(edge) {
+                    continue;
+                }
+                let (x2, y2) = center(coord + edge.to_hex2d());
+                writeln!(
+                body,
+                r#"  <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+                x1, y1, x2, y2, PATH_STROKE, HEX_SIZE * 0.25
+            )
+            .unwrap();
+            }
+        }
+        for &coord in &cells {
+            if board.get_junction_count(coord) != 0 {
+                let (x, y) = center(coord);
+                writeln!(
+                    body,
+                    r#"  <circle cx="{:.2}" cy="{:.2}" r="{}" fill="{}"/>"#,
+                    x,
+                    y,
+                    HEX_SIZE * 0.2,
+                    PATH_STROKE
+                )
+                .unwrap();
+            }
+        }
+
+        for (marks, flank) in puzzle.marks().iter().zip(FLANKS) {
+            // Away from the middle of the board
+            let (ox, oy) =
+                center(Coordinate::new(0, 0) - Coordinate::from(flank));
+            let len = (ox * ox + oy * oy).sqrt();
+            let (ox, oy) = (ox / len, oy / len);
+
+            for (i, markset) in marks.iter().enumerate() {
+                let (ax, ay) = center(puzzle.flank_anchor(flank, i));
+                // Nearest the board last, so they read outside-in
+                for (j, mark) in markset.iter().rev().enumerate() {
+                    let dist = HEX_SIZE * (1.3 + j as f32 * 0.8);
+                    let (x, y) = (ax + ox * dist, ay + oy * dist);
+                    bounds.add(x - HEX_SIZE * 0.5, y - HEX_SIZE * 0.5);
+                    bounds.add(x + HEX_SIZE * 0.5, y + HEX_SIZE * 0.5);
+                    writeln!(
+                    body,
+                    r#"  <text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="{}">{}</text>"#,
+                    x, y, HEX_SIZE * 0.7, MARK_FILL, mark
+                )
+                .unwrap();
+                }
+            }
+        }
+
+        let margin = HEX_SIZE * 0.5;
+        let (x, y) = (bounds.min_x - margin, bounds.min_y - margin);
+        let w = bounds.max_x - bounds.min_x + margin * 2.0;
+        let h = bounds.max_y - bounds.min_y + margin * 2.0;
+        Self {
+            view_box: [x, y, w, h],
+            body,
+        }
+    }
+
+    /// Width and height at 1 unit per pixel
+    pub fn size(&self) -> (f32, f32) {
+        (self.view_box[2], self.view_box[3])
+    }
+
+    pub fn to_svg(&self) -> String {
+        let [x, y, w, h] = self.view_box;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.2} {:.2} {:.2} {:.2}\" width=\"{:.0}\" height=\"{:.0}\">\n\
+             {}</svg>\n",
+            x, y, w, h, w, h, self.body
+        )
+    }
+
+    /// Nest this inside another SVG, scaled to fit in the given box and
+    /// centered in it.
+    pub fn placed(&self, x: f32, y: f32, w: f32, h: f32) -> String {
+        let [vx, vy, vw, vh] = self.view_box;
+        format!(
+            "<svg x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" viewBox=\"{:.2} {:.2} {:.2} {:.2}\">\n\
+             {}</svg>\n",
+            x, y, w, h, vx, vy, vw, vh, self.body
+        )
+    }
+}
+
+/// Same layout as the game, but with properly proportioned hexes.
+fn center(coord: Coordinate) -> (f32, f32) {
+    let x = coord.x as f32;
+    let z = (-coord.x - coord.y) as f32;
+    (SQRT_3 * HEX_SIZE * (x + z / 2.0), 1.5 * HEX_SIZE * z)
+}
+
+struct Bounds {
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            min_x: f32::INFINITY,
+            min_y: f32::INFINITY,
+            max_x: f32::NEG_INFINITY,
+            max_y: f32::NEG_INFINITY,
+        }
+    }
+}
+
+impl Bounds {
+    fn add(&mut self, x: f32, y: f32) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::from_ascii;
+
+    /// Starts with a newline to keep the drawing lined up in here
+    const PUZZLE: &str = "
+      2
+
+2   .   o
+         \\
+  .   .   o
+         /
+    #   o
+
+          1
+";
+
+    fn count(svg: &str, tag: &str) -> usize {
+        svg.matches(&format!("<{} ", tag)).count()
+    }
+
+    #[test]
+    fn draws_everything_once() {
+        let (puzzle, board) = from_ascii(PUZZLE).unwrap();
+        let svg = to_svg(&puzzle, &board);
+        assert_eq!(count(&svg, "polygon"), 7);
+        assert_eq!(count(&svg, "text"), 3);
+        assert_eq!(count(&svg, "line"), 2);
+        assert_eq!(count(&svg, "circle"), 3);
+    }
+
+    #[test]
+    fn dead_cells_are_left_out_of_the_field() {
+        let (puzzle, board) = from_ascii(PUZZLE).unwrap();
+        let svg = to_svg(&puzzle, &board);
+        assert_eq!(svg.matches(DEAD_FILL).count(), 1);
+        assert_eq!(svg.matches(FIELD_FILL).count(), 6);
+
+        let dead = puzzle.dead_cells().into_iter().next().unwrap();
+        let (x, y) = center(dead);
+        for end in ["1", "2"] {
+            let at = format!(r#"x{}="{:.2}" y{}="{:.2}""#, end, x, end, y);
+            assert!(!svg.contains(&at), "{}", at);
+        }
+    }
+
+    #[test]
+    fn placed_drawings_keep_their_contents() {
+        let (puzzle, board) = from_ascii(PUZZLE).unwrap();
+        let drawing = Drawing::new(&puzzle, &board);
+        let placed = drawing.placed(10.0, 20.0, 100.0, 50.0);
+        assert!(placed.starts_with(
+            r#"<svg x="10.00" y="20.00" width="100.00" height="50.00""#
+        ));
+        let whole = drawing.to_svg();
+        for tag in ["polygon", "text", "line", "circle"] {
+            assert_eq!(count(&placed, tag), count(&whole, tag), "{}", tag);
+        }
+    }
+}
@@ -1,21 +1,25 @@
 use macroquad::prelude::*;
 
-use crate::resources::Resources;
-
-use super::{
-    text::{draw_pixel_text, TextAlign},
-    util::mouse_position_pixel,
+use crate::{
+    input::{Action, Input},
+    resources::Resources,
+    sfx::SoundEvent,
 };
 
+use super::text::{draw_pixel_text, TextAlign};
+
 /// Button to be pressed
 #[derive(Debug, Clone)]
 pub struct Button {
     pub bounds: Rect,
     text: Option<(String, TextAlign)>,
-    /// Was the mouse on here last frame?
-    was_mouse_hovering: bool,
+    /// Was the mouse on here as of the last update?
+    hovering: bool,
+    /// Has the keyboard tabbed onto this?
+    focused: bool,
 }
 
+#[allow(dead_code)]
 impl Button {
     pub fn new_from_rect(
         bounds: Rect,
@@ -23,7 +27,8 @@ impl Button {
     ) -> Self {
         Self {
             bounds,
-            was_mouse_hovering: false,
+            hovering: false,
+            focused: false,
             text,
         }
     }
@@ -57,23 +62,65 @@ impl Button {
 
     /// You must call this at the *end* of every frame, after all
     /// processing is done.
-    pub fn post_update(&mut self) {
-        self.was_mouse_hovering = self.mouse_hovering();
+    pub fn post_update(&mut self, res: &Resources, input: &Input) {
+        if self.mouse_entered(input) {
+            res.sfx.emit(SoundEvent::ButtonHover);
+        }
+        self.hovering = self.hovered_by(input);
     }
 
-    /// Is the mouse currently over this?
+    /// Was the mouse over this as of the last update? Good for drawing.
     pub fn mouse_hovering(&self) -> bool {
-        self.bounds.contains(mouse_position_pixel())
+        self.hovering
+    }
+
+    /// Is the mouse over this this frame?
+    pub fn hovered_by(&self, input: &Input) -> bool {
+        self.bounds.contains(input.mouse)
     }
 
     /// Did the mouse enter the button this frame?
-    pub fn mouse_entered(&self) -> bool {
-        !self.was_mouse_hovering && self.mouse_hovering()
+    pub fn mouse_entered(&self, input: &Input) -> bool {
+        !self.hovering && self.hovered_by(input)
     }
 
     /// Did the mouse leave the button this frame?
-    pub fn mouse_left(&self) -> bool {
-        self.was_mouse_hovering && !self.mouse_hovering()
+    pub fn mouse_left(&self, input: &Input) -> bool {
+        self.hovering && !self.hovered_by(input)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Was this pressed with Enter (or the gamepad) while it had focus?
+    pub fn activated(&self, input: &Input) -> bool {
+        self.focused && input.pressed(Action::Confirm)
+    }
+
+    /// Was this clicked, tapped, or activated from the keyboard this frame?
+    pub fn pressed(&self, input: &Input) -> bool {
+        self.hovered_by(input) && input.mouse_pressed
+            || input.tapped(self.bounds)
+            || self.activated(input)
+    }
+
+    /// Draw a ring just outside the button if it has focus.
+    pub fn draw_focus_ring(&self, color: Color) {
+        if self.focused {
+            draw_rectangle_lines(
+                self.x().round() - 2.0,
+                self.y().round() - 2.0,
+                self.w().round() + 4.0,
+                self.h().round() + 4.0,
+                1.0,
+                color,
+            );
+        }
     }
 
     /// Quick-and-dirty drawing. `highlight` colors are for when the mouse is on the thing.
@@ -112,7 +159,38 @@ impl Button {
             }
             .round();
             let ty = (y + h / 2.0 - 2.5).round();
-            draw_pixel_text(s, tx, ty, *align, border, res.textures.font);
+            draw_pixel_text(s, tx, ty, *align, border, res);
         }
+        self.draw_focus_ring(border_highlight);
+    }
+}
+
+/// Move keyboard focus through the buttons, in order, with Tab and
+/// Shift+Tab. Nothing has focus until Tab is first pressed.
+pub fn tab_focus<'a>(
+    res: &Resources,
+    input: &Input,
+    buttons: impl IntoIterator<Item = &'a mut Button>,
+) {
+    let forwards = input.pressed(Action::FocusNext);
+    let backwards = input.pressed(Action::FocusPrev);
+    if !forwards && !backwards {
+        return;
+    }
+    let mut buttons = buttons.into_iter().collect::<Vec<_>>();
+    if buttons.is_empty() {
+        return;
+    }
+
+    let len = buttons.len();
+    let next = match buttons.iter().position(|b| b.focused) {
+        Some(idx) if backwards => (idx + len - 1) % len,
+        Some(idx) => (idx + 1) % len,
+        None if backwards => len - 1,
+        None => 0,
+    };
+    for (idx, b) in buttons.iter_mut().enumerate() {
+        b.focused = idx == next;
     }
+    res.sfx.emit(SoundEvent::ButtonHover);
 }
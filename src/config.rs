@@ -0,0 +1,164 @@
+//! Player settings, kept separately from the save so wiping progress
+//! doesn't wipe them too.
+
+use eyre::WrapErr;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::save::FileStorage;
+use crate::save::{MemoryStorage, SaveStorage};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigData {
+    /// From 0 to 1
+    pub music_volume: f32,
+    /// From 0 to 1
+    pub sfx_volume: f32,
+    pub fullscreen: bool,
+    /// Size of the window when not fullscreen
+    pub window_size: (i32, i32),
+    /// Only scale the canvas up by whole numbers, so every pixel is the
+    /// same size.
+    pub integer_scaling: bool,
+    /// Turn off wobbly text, bobbing and flashing.
+    pub reduced_motion: bool,
+    /// Swap the red and green highlights for ones that are easier to tell
+    /// apart.
+    pub colorblind: bool,
+    /// Locale code. `None` uses the default one.
+    pub language: Option<String>,
+}
+
+impl Default for ConfigData {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            fullscreen: false,
+            window_size: (960, 720),
+            integer_scaling: false,
+            reduced_motion: false,
+            colorblind: false,
+            language: None,
+        }
+    }
+}
+
+pub struct Config {
+    pub data: ConfigData,
+    storage: Box<dyn SaveStorage>,
+    /// When the window last changed size, if that hasn't been saved yet
+    resized_at: Option<f64>,
+}
+
+/// How long the window has to stay one size before it gets saved, so
+/// dragging its corner around doesn't write the file every frame.
+const RESIZE_SETTLE_SECS: f64 = 0.5;
+
+impl Config {
+    /// Load the config from the given storage, falling back to the defaults.
+    pub fn load(storage: Box<dyn SaveStorage>) -> Self {
+        let data = match Config::read(storage.as_ref()) {
+            Ok(Some(data)) => data,
+            Ok(None) => ConfigData::default(),
+            Err(e) => {
+                warn!("couldn't load config, using defaults: {:?}", e);
+                ConfigData::default()
+            }
+        };
+        Self {
+            data,
+            storage,
+            resized_at: None,
+        }
+    }
+
+    fn read(storage: &dyn SaveStorage) -> eyre::Result<Option<ConfigData>> {
+        let Some(src) = storage.load()? else {
+            return Ok(None);
+        };
+        let data = json5::from_str(&src).wrap_err("malformed config")?;
+        Ok(Some(data))
+    }
+
+    /// Load the config from the default storage for this platform.
+    pub fn open() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let storage: Box<dyn SaveStorage> =
+            match FileStorage::new("config.json5") {
+                Some(it) => Box::new(it),
+                None => Box::<MemoryStorage>::default(),
+            };
+        #[cfg(target_arch = "wasm32")]
+        let storage: Box<dyn SaveStorage> = Box::<MemoryStorage>::default();
+
+        Config::load(storage)
+    }
+
+    /// Write the config out to storage.
+    pub fn commit(&mut self) {
+        let res = json5::to_string(&self.data)
+            .wrap_err("couldn't serialize config")
+            .and_then(|s| self.storage.store(&s));
+        if let Err(e) = res {
+            warn!("couldn't write config: {:?}", e);
+        }
+    }
+
+    /// Call every frame with the window's size, to open at that size next
+    /// time.
+    pub fn track_window_size(&mut self, size: (i32, i32), now: f64) {
+        if self.data.fullscreen {
+            return;
+        }
+        if size != self.data.window_size {
+            self.data.window_size = size;
+            self.resized_at = Some(now);
+        } else if self
+            .resized_at
+            .is_some_and(|at| now - at >= RESIZE_SETTLE_SECS)
+        {
+            self.resized_at = None;
+            self.commit();
+        }
+    }
+}
+
+/// Switch the window in or out of fullscreen right now.
+pub fn apply_fullscreen(fullscreen: bool) {
+    // SAFETY: we don't touch the GL state, only the window.
+    let gl = unsafe { get_internal_gl() };
+    gl.quad_context.set_fullscreen(fullscreen);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(config: &Config) -> Option<ConfigData> {
+        Config::read(config.storage.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn window_size_is_saved_once_it_settles() {
+        let mut config = Config::load(Box::<MemoryStorage>::default());
+        config.track_window_size((800, 600), 0.0);
+        config.track_window_size((1024, 768), 0.1);
+        config.track_window_size((1024, 768), 0.2);
+        assert!(stored(&config).is_none());
+        config.track_window_size((1024, 768), 0.7);
+        assert_eq!(stored(&config).unwrap().window_size, (1024, 768));
+    }
+
+    #[test]
+    fn fullscreen_doesnt_count_as_a_window_size() {
+        let mut config = Config::load(Box::<MemoryStorage>::default());
+        config.data.fullscreen = true;
+        config.track_window_size((1920, 1080), 0.0);
+        config.track_window_size((1920, 1080), 1.0);
+        assert_eq!(config.data.window_size, ConfigData::default().window_size);
+        assert!(stored(&config).is_none());
+    }
+}
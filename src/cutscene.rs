@@ -0,0 +1,51 @@
+//! Scripted story beats that play between rows of levels.
+
+use ahash::AHashMap;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Cutscenes {
+    pub speakers: AHashMap<String, Speaker>,
+    /// The cutscene leading into each row of levels, indexed the same way
+    /// as `Levels::rows`.
+    pub scenes: Vec<Cutscene>,
+}
+
+impl Cutscenes {
+    pub fn get(&self, row: usize) -> Option<&Cutscene> {
+        self.scenes.get(row)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Speaker {
+    /// Key into the locale's `speakers`, so moods of the same character
+    /// can share a name
+    pub name: String,
+    /// Which 16x16 frame of `portraits.png` to draw
+    pub portrait: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Cutscene {
+    /// Where the UFO starts out
+    pub ufo: (f32, f32),
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// Show a line of dialogue and wait for the player to click through it.
+    Say {
+        /// Key into `Cutscenes::speakers`, or nobody for narration
+        #[serde(default)]
+        speaker: Option<String>,
+        /// Key into the locale's `dialogue`
+        line: String,
+    },
+    /// Wait this many frames.
+    Pause(u32),
+    /// Fly the UFO to the given spot over this many frames.
+    MoveUfo { x: f32, y: f32, frames: u32 },
+}
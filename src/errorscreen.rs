@@ -0,0 +1,69 @@
+//! What to show when the resources are broken. Nothing from them can be
+//! used, so this sticks to macroquad's built-in font.
+
+use macroquad::prelude::*;
+
+use crate::{resources::LoadError, util::hexcolor};
+
+const FONT_SIZE: f32 = 20.0;
+const MARGIN: f32 = 16.0;
+
+/// Show everything that went wrong until the player closes it.
+pub async fn show_load_errors(errors: &[LoadError]) {
+    loop {
+        if is_key_pressed(KeyCode::Escape)
+            || is_key_pressed(KeyCode::Enter)
+            || is_mouse_button_pressed(MouseButton::Left)
+        {
+            return;
+        }
+
+        clear_background(hexcolor(0x42756bff));
+        let width = screen_width() - MARGIN * 2.0;
+        let mut y = MARGIN + FONT_SIZE;
+        let mut line = |text: &str, color: Color| {
+            for wrapped in wrap(text, width) {
+                draw_text(&wrapped, MARGIN, y, FONT_SIZE, color);
+                y += FONT_SIZE;
+            }
+        };
+
+        line("Some of the game's files couldn't be loaded:", WHITE);
+        for err in errors {
+            line("", WHITE);
+            let path = match err.location {
+                Some((row, col)) => format!("{}:{}:{}", err.path, row, col),
+                None => err.path.clone(),
+            };
+            line(&path, hexcolor(0x99e8e0ff));
+            line(&format!("{:#}", err.error), hexcolor(0xe36e64ff));
+            line(&err.suggestion, WHITE);
+        }
+        line("", WHITE);
+        line("Press Escape to quit.", hexcolor(0xa3b3a2ff));
+
+        next_frame().await;
+    }
+}
+
+/// Break text into lines that fit in the width, at spaces.
+fn wrap(text: &str, width: f32) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split(' ') {
+        let last = lines.last_mut().unwrap();
+        let candidate = if last.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{} {}", last, word)
+        };
+        if last.is_empty()
+            || measure_text(&candidate, None, FONT_SIZE as u16, 1.0).width
+                <= width
+        {
+            *last = candidate;
+        } else {
+            lines.push(word.to_owned());
+        }
+    }
+    lines
+}
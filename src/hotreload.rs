@@ -0,0 +1,128 @@
+//! In debug builds, watch the puzzles and textures on disk and swap them in
+//! when they change, so they can be tweaked without restarting.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use eyre::bail;
+use macroquad::prelude::*;
+
+use crate::{
+    puzzle::Level,
+    resources::{decode_texture, Levels, Resources, RESOURCES_ROOT},
+};
+
+/// How many frames to wait between looking at the files
+const POLL_EVERY: u32 = 30;
+
+enum Watched {
+    Puzzles,
+    /// By the name it's loaded under
+    Texture(String),
+}
+
+pub struct HotReload {
+    files: Vec<(PathBuf, Watched, Option<SystemTime>)>,
+    timer: u32,
+}
+
+impl HotReload {
+    pub fn new() -> Self {
+        let root = Path::new(RESOURCES_ROOT);
+        let mut files = vec![(root.join("puzzles.json5"), Watched::Puzzles)];
+        match fs::read_dir(root.join("textures")) {
+            Ok(dir) => {
+                for path in dir.flatten().map(|entry| entry.path()) {
+                    let name = path.file_stem().and_then(|s| s.to_str());
+                    if let (Some(name), Some("png")) =
+                        (name, path.extension().and_then(|s| s.to_str()))
+                    {
+                        let name = name.to_owned();
+                        files.push((path, Watched::Texture(name)));
+                    }
+                }
+            }
+            Err(e) => warn!("can't watch textures: {}", e),
+        }
+
+        let files = files
+            .into_iter()
+            .map(|(path, watched)| {
+                let mtime = modified(&path);
+                (path, watched, mtime)
+            })
+            .collect();
+        Self { files, timer: 0 }
+    }
+
+    /// Call once a frame.
+    pub fn poll(&mut self, res: &mut Resources) {
+        self.timer += 1;
+        if self.timer < POLL_EVERY {
+            return;
+        }
+        self.timer = 0;
+
+        for (path, watched, mtime) in self.files.iter_mut() {
+            let now = modified(path);
+            if now == *mtime {
+                continue;
+            }
+            *mtime = now;
+
+            info!("reloading {}", path.display());
+            let res = match watched {
+                Watched::Puzzles => reload_puzzles(res, path),
+                Watched::Texture(name) => reload_texture(res, path, name),
+            };
+            if let Err(e) = res {
+                warn!("couldn't reload {}: {:?}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn reload_puzzles(res: &mut Resources, path: &Path) -> eyre::Result<()> {
+    let src = fs::read_to_string(path)?;
+    let rows: Vec<Vec<Level>> = json5::from_str(&src)?;
+
+    // The level select and the save both index levels by position, so
+    // only edits in place are safe
+    let same_layout = rows.len() == res.levels.rows.len()
+        && rows
+            .iter()
+            .zip(&res.levels.rows)
+            .all(|(new, old)| new.len() == old.len());
+    if !same_layout {
+        bail!("levels were added or removed, restart to see them");
+    }
+    res.levels = Levels::new(rows);
+    Ok(())
+}
+
+fn reload_texture(
+    res: &mut Resources,
+    path: &Path,
+    name: &str,
+) -> eyre::Result<()> {
+    let bytes = fs::read(path)?;
+    // An editor might be halfway through writing it, in which case it'll
+    // fail now and get picked up again once it's done
+    let tex = decode_texture(&bytes)?;
+
+    let Some(slot) = res.textures.by_name_mut(name) else {
+        // Not one we use
+        tex.delete();
+        return Ok(());
+    };
+    slot.delete();
+    *slot = tex;
+    Ok(())
+}
@@ -0,0 +1,433 @@
+//! Buttons the player can press, whatever they're pressing them with.
+//!
+//! Each frame an [`InputSource`] boils everything down to an [`Input`], which
+//! gets handed to the active state. Game code asks about [`Action`]s instead
+//! of specific keys, so the keyboard and a gamepad can both drive everything.
+//! Nothing outside of this module reads macroquad's input globals.
+
+#[cfg(any(
+    feature = "gamepad",
+    not(any(target_os = "linux", target_arch = "wasm32"))
+))]
+mod gamepad;
+
+/// Stands in when gamepad support isn't built in; see `Cargo.toml`.
+#[cfg(not(any(
+    feature = "gamepad",
+    not(any(target_os = "linux", target_arch = "wasm32"))
+)))]
+mod gamepad {
+    use enumflags2::BitFlags;
+    use macroquad::prelude::Vec2;
+
+    use super::PadButton;
+
+    pub struct Inner;
+
+    impl Inner {
+        pub fn new() -> Option<Self> {
+            None
+        }
+
+        pub fn poll(&mut self) -> (BitFlags<PadButton>, Vec2) {
+            (BitFlags::empty(), Vec2::ZERO)
+        }
+    }
+}
+
+use std::{collections::VecDeque, f32::consts::TAU};
+
+use enumflags2::{bitflags, BitFlags};
+use hex2d::Direction;
+use macroquad::prelude::*;
+
+use crate::util::screen_to_pixel;
+
+/// Keys that walk the keyboard cursor, laid out like a hexagon around S.
+/// Hold shift to move without drawing.
+const CURSOR_KEYS: [(KeyCode, Direction); 6] = [
+    (KeyCode::D, Direction::XY),
+    (KeyCode::C, Direction::ZY),
+    (KeyCode::Z, Direction::ZX),
+    (KeyCode::A, Direction::YX),
+    (KeyCode::Q, Direction::YZ),
+    (KeyCode::E, Direction::XZ),
+];
+
+/// How far outside a button a tap can land and still press it, in pixels.
+const TAP_SLOP: f32 = 2.0;
+
+/// How far the stick has to be pushed before it counts.
+const STICK_DEADZONE: f32 = 0.5;
+/// Frames to hold a direction before it starts repeating.
+const WALK_REPEAT_DELAY: u32 = 18;
+/// Frames between repeats after that.
+const WALK_REPEAT_EVERY: u32 = 8;
+
+#[bitflags]
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Press whatever has focus
+    Confirm,
+    /// Leave the current screen
+    Back,
+    FocusNext,
+    FocusPrev,
+    /// Turn the focused setting up
+    Increase,
+    /// Turn the focused setting down
+    Decrease,
+    /// See if the board is solved
+    Check,
+    /// Flip to the next page of the level description
+    NextPage,
+    Undo,
+    Redo,
+    Pause,
+    /// Save the puzzle as an SVG
+    Export,
+}
+
+/// One step of the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Walk {
+    pub dir: Direction,
+    /// Should the path walked over be toggled?
+    pub draw: bool,
+}
+
+/// Everything the states get to know about the player for one frame.
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    /// Mouse position on the canvas
+    pub mouse: Vec2,
+    /// Is the left button held?
+    pub mouse_down: bool,
+    /// Did the left button go down this frame?
+    pub mouse_pressed: bool,
+    /// Did the right button go down this frame?
+    pub right_pressed: bool,
+    /// Fingers on the screen, with positions on the canvas
+    pub touches: Vec<Touch>,
+    /// Actions started this frame
+    pub actions: BitFlags<Action>,
+    /// Which way the cursor should step this frame, if at all
+    pub walk: Option<Walk>,
+}
+
+impl Input {
+    /// Was the action started this frame?
+    pub fn pressed(&self, action: Action) -> bool {
+        self.actions.contains(action)
+    }
+
+    /// Did a finger come down on (or near) this rectangle this frame?
+    pub fn tapped(&self, bounds: Rect) -> bool {
+        let bounds = Rect::new(
+            bounds.x - TAP_SLOP,
+            bounds.y - TAP_SLOP,
+            bounds.w + TAP_SLOP * 2.0,
+            bounds.h + TAP_SLOP * 2.0,
+        );
+        self.touches.iter().any(|t| {
+            t.phase == TouchPhase::Started && bounds.contains(t.position)
+        })
+    }
+}
+
+/// Somewhere to get input from, once a frame.
+pub trait InputSource {
+    fn next_input(&mut self) -> Input;
+}
+
+/// Gamepad buttons we care about.
+#[bitflags]
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// All the gamepads mashed together into one.
+#[derive(Debug, Clone, Copy, Default)]
+struct PadState {
+    held: BitFlags<PadButton>,
+    pressed: BitFlags<PadButton>,
+    /// Left stick, with +y pointing down the screen
+    stick: Vec2,
+    /// Which way the stick or d-pad has been held, and for how long
+    walk_dir: Option<Direction>,
+    walk_frames: u32,
+}
+
+/// Reads the real mouse, keyboard, touchscreen and any plugged-in gamepads.
+pub struct MacroquadInput {
+    gamepads: Option<gamepad::Inner>,
+    pad: PadState,
+    /// Keep this in step with the config, so the mouse lands in the right
+    /// spot on the canvas
+    pub integer_scaling: bool,
+}
+
+impl MacroquadInput {
+    pub fn new() -> Self {
+        Self {
+            gamepads: gamepad::Inner::new(),
+            pad: PadState::default(),
+            integer_scaling: false,
+        }
+    }
+
+    fn poll_pads(&mut self) {
+        if let Some(gamepads) = &mut self.gamepads {
+            let (held, stick) = gamepads.poll();
+            self.pad.pressed = held & !self.pad.held;
+            self.pad.held = held;
+            self.pad.stick = stick;
+        }
+
+        let dir = pad_direction(&self.pad);
+        if dir.is_some() && dir == self.pad.walk_dir {
+            self.pad.walk_frames += 1;
+        } else {
+            self.pad.walk_dir = dir;
+            self.pad.walk_frames = 0;
+        }
+    }
+
+    fn actions(&self) -> BitFlags<Action> {
+        let shift =
+            is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let ctrl = is_key_down(KeyCode::LeftControl)
+            || is_key_down(KeyCode::RightControl);
+
+        let mut actions = BitFlags::empty();
+        for action in BitFlags::<Action>::all() {
+            let key = match action {
+                Action::Confirm => is_key_pressed(KeyCode::Enter),
+                Action::Back => is_key_pressed(KeyCode::Escape),
+                Action::FocusNext => !shift && is_key_pressed(KeyCode::Tab),
+                Action::FocusPrev => shift && is_key_pressed(KeyCode::Tab),
+                Action::Increase => is_key_pressed(KeyCode::Right),
+                Action::Decrease => is_key_pressed(KeyCode::Left),
+                Action::Check => is_key_pressed(KeyCode::Space),
+                Action::NextPage => is_key_pressed(KeyCode::Enter),
+                Action::Undo => ctrl && !shift && is_key_pressed(KeyCode::Z),
+                Action::Redo => {
+                    ctrl && (is_key_pressed(KeyCode::Y)
+                        || shift && is_key_pressed(KeyCode::Z))
+                }
+                Action::Pause => false,
+                Action::Export => ctrl && is_key_pressed(KeyCode::E),
+            };
+            let pad = match action {
+                Action::Confirm => Some(PadButton::South),
+                Action::Back => Some(PadButton::East),
+                Action::FocusNext => Some(PadButton::DPadDown),
+                Action::FocusPrev => Some(PadButton::DPadUp),
+                Action::Increase => Some(PadButton::DPadRight),
+                Action::Decrease => Some(PadButton::DPadLeft),
+                Action::Check => Some(PadButton::North),
+                Action::NextPage => Some(PadButton::West),
+                Action::Undo => Some(PadButton::LeftBumper),
+                Action::Redo => Some(PadButton::RightBumper),
+                Action::Pause => Some(PadButton::Start),
+                Action::Export => None,
+            };
+            if key || pad.is_some_and(|pad| self.pad.pressed.contains(pad)) {
+                actions |= action;
+            }
+        }
+        actions
+    }
+
+    /// The keyboard draws as it goes unless shift is held; the gamepad only
+    /// draws while the south face button is held.
+    fn walk(&self) -> Option<Walk> {
+        let sneaking =
+            is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let ctrl = is_key_down(KeyCode::LeftControl)
+            || is_key_down(KeyCode::RightControl);
+        let key = CURSOR_KEYS
+            .iter()
+            .find(|(key, _)| !ctrl && is_key_pressed(*key))
+            .map(|(_, dir)| Walk {
+                dir: *dir,
+                draw: !sneaking,
+            });
+        if key.is_some() {
+            return key;
+        }
+
+        let frames = self.pad.walk_frames;
+        let step = frames == 0
+            || frames >= WALK_REPEAT_DELAY
+                && (frames - WALK_REPEAT_DELAY)
+                    .is_multiple_of(WALK_REPEAT_EVERY);
+        self.pad.walk_dir.filter(|_| step).map(|dir| Walk {
+            dir,
+            draw: self.pad.held.contains(PadButton::South),
+        })
+    }
+}
+
+impl InputSource for MacroquadInput {
+    fn next_input(&mut self) -> Input {
+        self.poll_pads();
+        let to_canvas = |pos| screen_to_pixel(pos, self.integer_scaling);
+        Input {
+            mouse: to_canvas(mouse_position().into()),
+            mouse_down: is_mouse_button_down(MouseButton::Left),
+            mouse_pressed: is_mouse_button_pressed(MouseButton::Left),
+            right_pressed: is_mouse_button_pressed(MouseButton::Right),
+            touches: touches()
+                .into_iter()
+                .map(|t| Touch {
+                    position: to_canvas(t.position),
+                    ..t
+                })
+                .collect(),
+            actions: self.actions(),
+            walk: self.walk(),
+        }
+    }
+}
+
+/// The nearest hex direction to wherever the stick or d-pad is pointing.
+/// Diagonals on the d-pad pick out the slanted directions.
+fn pad_direction(state: &PadState) -> Option<Direction> {
+    let aim = if state.stick.length() >= STICK_DEADZONE {
+        state.stick
+    } else {
+        let mut aim = Vec2::ZERO;
+        for (button, delta) in [
+            (PadButton::DPadRight, vec2(1.0, 0.0)),
+            (PadButton::DPadLeft, vec2(-1.0, 0.0)),
+            (PadButton::DPadDown, vec2(0.0, 1.0)),
+            (PadButton::DPadUp, vec2(0.0, -1.0)),
+        ] {
+            if state.held.contains(button) {
+                aim += delta;
+            }
+        }
+        aim
+    };
+    if aim == Vec2::ZERO {
+        return None;
+    }
+
+    let angle = aim.y.atan2(aim.x);
+    let clean_angle = ((angle / TAU) * 6.0).round() as i32;
+    Some(Direction::from_int(clean_angle + 2))
+}
+
+/// Plays back a prepared list of frames, then sits still forever after.
+///
+/// Build one up by chaining, like
+/// `ScriptedInput::new().wait(10).drag(a, b, 20).press(Action::Check)`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    frames: VecDeque<Input>,
+    /// Where the mouse was left by the last frame added
+    mouse: Vec2,
+}
+
+impl ScriptedInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a frame exactly as given.
+    pub fn frame(mut self, input: Input) -> Self {
+        self.mouse = input.mouse;
+        self.frames.push_back(input);
+        self
+    }
+
+    /// Are there any frames left?
+    pub fn is_done(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Shorthands for writing out what the player does in tests.
+#[cfg(test)]
+impl ScriptedInput {
+    /// Do nothing for a while.
+    pub fn wait(self, frames: usize) -> Self {
+        let mouse = self.mouse;
+        (0..frames).fold(self, |this, _| {
+            this.frame(Input {
+                mouse,
+                ..Default::default()
+            })
+        })
+    }
+
+    /// Start an action for one frame.
+    pub fn press(self, action: Action) -> Self {
+        let mouse = self.mouse;
+        self.frame(Input {
+            mouse,
+            actions: action.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Step the cursor once.
+    pub fn walk(self, dir: Direction, draw: bool) -> Self {
+        let mouse = self.mouse;
+        self.frame(Input {
+            mouse,
+            walk: Some(Walk { dir, draw }),
+            ..Default::default()
+        })
+    }
+
+    /// Click the left mouse button somewhere and let go the next frame.
+    pub fn click(self, pos: Vec2) -> Self {
+        self.frame(Input {
+            mouse: pos,
+            mouse_down: true,
+            mouse_pressed: true,
+            ..Default::default()
+        })
+        .wait(1)
+    }
+
+    /// Hold the left mouse button and drag in a straight line over the given
+    /// number of frames, then let go.
+    pub fn drag(self, from: Vec2, to: Vec2, frames: usize) -> Self {
+        let frames = frames.max(1);
+        (0..=frames)
+            .fold(self, |this, i| {
+                this.frame(Input {
+                    mouse: from.lerp(to, i as f32 / frames as f32),
+                    mouse_down: true,
+                    mouse_pressed: i == 0,
+                    ..Default::default()
+                })
+            })
+            .wait(1)
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn next_input(&mut self) -> Input {
+        self.frames.pop_front().unwrap_or_else(|| Input {
+            mouse: self.mouse,
+            ..Default::default()
+        })
+    }
+}
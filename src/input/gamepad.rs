@@ -0,0 +1,60 @@
+use enumflags2::BitFlags;
+use gilrs::{Axis, Button, Gilrs};
+use macroquad::prelude::*;
+
+use super::PadButton;
+
+const BUTTONS: [(Button, PadButton); 11] = [
+    (Button::South, PadButton::South),
+    (Button::East, PadButton::East),
+    (Button::West, PadButton::West),
+    (Button::North, PadButton::North),
+    (Button::LeftTrigger, PadButton::LeftBumper),
+    (Button::RightTrigger, PadButton::RightBumper),
+    (Button::Start, PadButton::Start),
+    (Button::DPadUp, PadButton::DPadUp),
+    (Button::DPadDown, PadButton::DPadDown),
+    (Button::DPadLeft, PadButton::DPadLeft),
+    (Button::DPadRight, PadButton::DPadRight),
+];
+
+pub struct Inner {
+    gilrs: Gilrs,
+}
+
+impl Inner {
+    /// Returns `None` if there's no way to talk to gamepads on this system.
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs }),
+            Err(e) => {
+                warn!("couldn't start gamepad support: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Return what's held down across every gamepad, and the furthest any
+    /// left stick is pushed.
+    pub fn poll(&mut self) -> (BitFlags<PadButton>, Vec2) {
+        // Gilrs only updates its cached state as events are pulled out
+        while self.gilrs.next_event().is_some() {}
+
+        let mut held = BitFlags::empty();
+        let mut stick = Vec2::ZERO;
+        for (_, pad) in self.gilrs.gamepads() {
+            for (button, ours) in BUTTONS {
+                if pad.is_pressed(button) {
+                    held |= ours;
+                }
+            }
+            // Gilrs has +y going up
+            let here =
+                vec2(pad.value(Axis::LeftStickX), -pad.value(Axis::LeftStickY));
+            if here.length() > stick.length() {
+                stick = here;
+            }
+        }
+        (held, stick)
+    }
+}
@@ -0,0 +1,98 @@
+//! Translated text.
+
+use ahash::AHashMap;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Locale {
+    /// Name of the language, written in that language
+    pub name: String,
+    #[serde(default)]
+    pub ui: AHashMap<String, String>,
+    /// Level descriptions, keyed by level id
+    #[serde(default)]
+    pub levels: AHashMap<String, String>,
+    /// Names of the people in cutscenes
+    #[serde(default)]
+    pub speakers: AHashMap<String, String>,
+    /// Every line said in a cutscene
+    #[serde(default)]
+    pub dialogue: AHashMap<String, String>,
+}
+
+pub struct Locales {
+    /// Language codes and their strings. The first one is the fallback for
+    /// anything the others are missing.
+    locales: Vec<(String, Locale)>,
+    current: usize,
+}
+
+impl Locales {
+    pub fn new(locales: Vec<(String, Locale)>) -> Self {
+        assert!(!locales.is_empty(), "must have a fallback locale");
+        Self {
+            locales,
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> &Locale {
+        &self.locales[self.current].1
+    }
+
+    /// Code of the language in use, like `en`.
+    pub fn current_code(&self) -> &str {
+        &self.locales[self.current].0
+    }
+
+    /// Switch to the language with the given code, if we have it.
+    pub fn set_current(&mut self, code: &str) -> bool {
+        match self.locales.iter().position(|(c, _)| c == code) {
+            Some(idx) => {
+                self.current = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Switch to the next language along.
+    pub fn cycle(&mut self) {
+        self.current = (self.current + 1) % self.locales.len();
+    }
+
+    /// Switch to the previous language.
+    pub fn cycle_back(&mut self) {
+        self.current =
+            (self.current + self.locales.len() - 1) % self.locales.len();
+    }
+
+    /// Look up a UI string, falling back to the key itself.
+    pub fn ui<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(|l| l.ui.get(key)).unwrap_or(key)
+    }
+
+    /// Look up a level's description, falling back to the level id itself.
+    pub fn level_description<'a>(&'a self, id: &'a str) -> &'a str {
+        self.lookup(|l| l.levels.get(id)).unwrap_or(id)
+    }
+
+    /// Look up a cutscene speaker's name, falling back to the key itself.
+    pub fn speaker_name<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(|l| l.speakers.get(key)).unwrap_or(key)
+    }
+
+    /// Look up a line of dialogue, falling back to the key itself.
+    pub fn dialogue<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(|l| l.dialogue.get(key)).unwrap_or(key)
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        f: impl Fn(&'a Locale) -> Option<&'a String>,
+    ) -> Option<&'a str> {
+        f(self.current())
+            .or_else(|| f(&self.locales[0].1))
+            .map(String::as_str)
+    }
+}
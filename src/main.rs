@@ -1,26 +1,49 @@
-#![feature(array_methods)]
 #![feature(macro_metavar_expr)]
+// Colors are written RRGGBB_AA
+#![allow(clippy::unusual_byte_groupings)]
 
 mod button;
-mod geom;
-mod puzzle;
+mod config;
+mod cutscene;
+mod errorscreen;
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+mod hotreload;
+mod input;
+mod locale;
+mod music;
+mod replay;
 mod resources;
+mod save;
+mod sfx;
 mod states;
 mod text;
 mod util;
 
+use config::Config;
+use crop_circles_core::{generate, geom, puzzle, svg};
 use gerrymander::StateMachine;
-use resources::Resources;
+use input::{InputSource, MacroquadInput};
+use music::MusicPlayer;
+use resources::{Audio, LoadError, LoadProgress, Resources};
+use save::Save;
 use states::{GameState, GameStateDispatch};
+use std::sync::{Arc, Mutex};
 
-use macroquad::prelude::{coroutines::start_coroutine, *};
-use util::wh_deficit;
+use macroquad::{
+    audio::{play_sound, PlaySoundParams},
+    prelude::{coroutines::start_coroutine, *},
+};
+use util::{hexcolor, wh_deficit};
 
 const WIDTH: f32 = 320.0;
 const HEIGHT: f32 = 240.0;
 const ASPECT_RATIO: f32 = WIDTH / HEIGHT;
 
 fn window_conf() -> Conf {
+    // This runs before `main`, so there's nowhere to keep this. `main` just
+    // loads it again.
+    let config = Config::open();
+    let (window_width, window_height) = config.data.window_size;
     Conf {
         window_title: if cfg!(debug_assertions) {
             concat!(env!("CARGO_CRATE_NAME"), " v", env!("CARGO_PKG_VERSION"))
@@ -28,7 +51,9 @@ fn window_conf() -> Conf {
             "Crop Circles"
         }
         .to_owned(),
-        fullscreen: false,
+        fullscreen: config.data.fullscreen,
+        window_width,
+        window_height,
         sample_count: 16,
         ..Default::default()
     }
@@ -39,33 +64,63 @@ async fn main() {
     let canvas = render_target(WIDTH as u32, HEIGHT as u32);
     canvas.texture.set_filter(FilterMode::Nearest);
 
-    load_and_anim(canvas).await;
-
-    let mut states = StateMachine::new(GameState::start());
+    let (mut res, audio) = match load_and_anim(canvas).await {
+        Ok(it) => it,
+        Err(errors) => {
+            errorscreen::show_load_errors(&errors).await;
+            return;
+        }
+    };
+
+    res.recorder.store_on_panic();
+    let mut states = StateMachine::new(GameState::start(&res));
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(state) = replay_from_args(&mut res) {
+        states
+            .apply(gerrymander::Transition::Push(GameState::Replay(state)))
+            .unwrap();
+    }
+    let mut music = MusicPlayer::new();
+    let mut input_source = MacroquadInput::new();
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    let mut hot_reload = hotreload::HotReload::new();
 
     loop {
-        let trans = states.active_mut().update();
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        hot_reload.poll(&mut res);
+
+        let window_size = (screen_width() as i32, screen_height() as i32);
+        res.config.track_window_size(window_size, get_time());
+        input_source.integer_scaling = res.config.data.integer_scaling;
+        let input = input_source.next_input();
+        let trans = states.active_mut().update(&mut res, &input);
         states.apply(trans).unwrap();
 
+        play_sounds(&res, &audio);
+        {
+            let active = states.active();
+            let track = active.music().map(|track| audio.music.get(track));
+            let volume = res.config.data.music_volume;
+            music.update(track, active.ducks_music(), volume);
+        }
+
         set_camera(&Camera2D {
             render_target: Some(canvas),
-            zoom: vec2(
-                (WIDTH as f32).recip() * 2.0,
-                (HEIGHT as f32).recip() * 2.0,
-            ),
-            target: vec2(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0),
+            zoom: vec2(WIDTH.recip() * 2.0, HEIGHT.recip() * 2.0),
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
             ..Default::default()
         });
         clear_background(WHITE);
 
-        states.active().draw();
+        states.active().draw(&res);
 
         set_default_camera();
         clear_background(BLACK);
 
         // Figure out the drawbox.
         // these are how much wider/taller the window is than the content
-        let (width_deficit, height_deficit) = wh_deficit();
+        let (width_deficit, height_deficit) =
+            wh_deficit(res.config.data.integer_scaling);
         draw_texture_ex(
             canvas.texture,
             width_deficit / 2.0,
@@ -84,29 +139,85 @@ async fn main() {
     }
 }
 
-async fn load_and_anim(canvas: RenderTarget) {
-    let coro = start_coroutine(Resources::init());
+/// `--replay <file>` jumps straight into watching it.
+#[cfg(not(target_arch = "wasm32"))]
+fn replay_from_args(res: &mut Resources) -> Option<states::StateReplay> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--replay");
+    args.next()?;
+    let Some(path) = args.next() else {
+        warn!("--replay needs a file to play");
+        return None;
+    };
+    match replay::Replay::load(&path)
+        .and_then(|replay| states::StateReplay::new(res, &replay))
+    {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("couldn't play {}: {:?}", path, e);
+            None
+        }
+    }
+}
+
+fn play_sounds(res: &Resources, audio: &Audio) {
+    let volume = res.config.data.sfx_volume;
+    let mut events = res.sfx.drain();
+    // Don't stack up the same sound on itself
+    events.sort_unstable_by_key(|ev| *ev as u8);
+    events.dedup();
+    for ev in events {
+        play_sound(
+            audio.sounds.get(ev),
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+    }
+}
+
+async fn load_and_anim(
+    canvas: RenderTarget,
+) -> Result<(Resources, Audio), Vec<LoadError>> {
+    let config = Config::open();
+    let integer_scaling = config.data.integer_scaling;
+    let progress = Arc::new(Mutex::new(LoadProgress::default()));
+    let coro = start_coroutine(Resources::load(
+        Save::open(),
+        config,
+        progress.clone(),
+    ));
 
     while !coro.is_done() {
         set_camera(&Camera2D {
             render_target: Some(canvas),
-            zoom: vec2(
-                (WIDTH as f32).recip() * 2.0,
-                (HEIGHT as f32).recip() * 2.0,
-            ),
-            target: vec2(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0),
+            zoom: vec2(WIDTH.recip() * 2.0, HEIGHT.recip() * 2.0),
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
             ..Default::default()
         });
-        clear_background(GREEN);
-
-        draw_text("Loading!", 20.0, 20.0, 16.0, BLACK);
+        clear_background(hexcolor(0x8bb392ff));
+
+        // No font texture yet, so it's the built-in one
+        let progress = progress.lock().unwrap().clone();
+        let (bar_x, bar_y, bar_w, bar_h) =
+            (40.0, HEIGHT / 2.0, WIDTH - 80.0, 8.0);
+        draw_text("Loading!", bar_x, bar_y - 8.0, 16.0, BLACK);
+        let frac = if progress.total == 0 {
+            0.0
+        } else {
+            progress.done as f32 / progress.total as f32
+        };
+        draw_rectangle(bar_x, bar_y, bar_w, bar_h, hexcolor(0x42756bff));
+        draw_rectangle(bar_x, bar_y, bar_w * frac, bar_h, hexcolor(0x48cfafff));
+        draw_rectangle_lines(bar_x, bar_y, bar_w, bar_h, 2.0, BLACK);
+        draw_text(&progress.current, bar_x, bar_y + bar_h + 12.0, 12.0, BLACK);
 
         set_default_camera();
         clear_background(BLACK);
 
         // Figure out the drawbox.
         // these are how much wider/taller the window is than the content
-        let (width_deficit, height_deficit) = wh_deficit();
+        let (width_deficit, height_deficit) = wh_deficit(integer_scaling);
         draw_texture_ex(
             canvas.texture,
             width_deficit / 2.0,
@@ -123,4 +234,6 @@ async fn load_and_anim(canvas: RenderTarget) {
 
         next_frame().await;
     }
+
+    coro.retrieve().unwrap()
 }
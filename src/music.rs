@@ -0,0 +1,99 @@
+//! Background music that keeps going across state changes.
+
+use macroquad::audio::{
+    play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+
+/// Volume to play at while something's talking over the music.
+const DUCKED_VOLUME: f32 = 0.35;
+/// How much volume changes per frame while fading.
+const FADE_SPEED: f32 = 1.0 / 90.0;
+
+/// Which piece of music a state wants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    Menu,
+    /// The music for a row of levels
+    Row(usize),
+}
+
+struct Playing {
+    sound: Sound,
+    volume: f32,
+}
+
+/// Lives outside of the state machine so that the music doesn't restart
+/// every time the state changes.
+pub struct MusicPlayer {
+    current: Option<Playing>,
+    /// Old tracks on their way out
+    fading: Vec<Playing>,
+}
+
+impl MusicPlayer {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            fading: Vec::new(),
+        }
+    }
+
+    /// Call once a frame with what should be playing. Changing tracks
+    /// crossfades between them.
+    ///
+    /// `volume` is the player's music volume setting, from 0 to 1.
+    pub fn update(&mut self, wanted: Option<Sound>, ducked: bool, volume: f32) {
+        let same = match (&self.current, wanted) {
+            (Some(playing), Some(wanted)) => playing.sound == wanted,
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            if let Some(old) = self.current.take() {
+                self.fading.push(old);
+            }
+            if let Some(sound) = wanted {
+                // If it was on its way out, pick it back up where it was
+                let playing =
+                    match self.fading.iter().position(|p| p.sound == sound) {
+                        Some(idx) => self.fading.remove(idx),
+                        None => {
+                            play_sound(
+                                sound,
+                                PlaySoundParams {
+                                    looped: true,
+                                    volume: 0.0,
+                                },
+                            );
+                            Playing { sound, volume: 0.0 }
+                        }
+                    };
+                self.current = Some(playing);
+            }
+        }
+
+        if let Some(playing) = &mut self.current {
+            let target = if ducked { DUCKED_VOLUME } else { 1.0 };
+            playing.volume = approach(playing.volume, target, FADE_SPEED);
+            set_sound_volume(playing.sound, playing.volume * volume);
+        }
+        self.fading.retain_mut(|playing| {
+            playing.volume = approach(playing.volume, 0.0, FADE_SPEED);
+            if playing.volume <= 0.0 {
+                stop_sound(playing.sound);
+                false
+            } else {
+                set_sound_volume(playing.sound, playing.volume * volume);
+                true
+            }
+        });
+    }
+}
+
+fn approach(from: f32, to: f32, step: f32) -> f32 {
+    if from < to {
+        (from + step).min(to)
+    } else {
+        (from - step).max(to)
+    }
+}
@@ -0,0 +1,265 @@
+//! Recording everything the player does in a level, to play it back exactly
+//! later. Good for reproducing bug reports and watching solves.
+//!
+//! Desktop builds keep the last attempt at each level in the `replays`
+//! folder next to the save. Play one back with `--replay <file>`.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use enumflags2::BitFlags;
+#[cfg(not(target_arch = "wasm32"))]
+use eyre::WrapErr;
+use hex2d::Direction;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Action, Input, ScriptedInput, Walk},
+    puzzle::Board,
+};
+
+/// Bump this whenever the layout of [`Replay`] changes.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// How often to write out the recording while it's going, in case the game
+/// gets killed. It's a 60hz update.
+const STORE_EVERY_FRAMES: u64 = 60 * 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// Id of the level pack, or `None` for the built-in levels
+    pub pack: Option<String>,
+    /// Id of the level, which stays put even if the level list gets
+    /// shuffled around
+    pub level: String,
+    /// The day, if it's the daily puzzle
+    #[serde(default)]
+    pub daily: Option<i64>,
+    /// The board as it was when the recording started
+    pub start: Board,
+    /// How many frames long it is
+    pub frames: u64,
+    /// Every frame the input changed on, with what it changed to
+    pub events: Vec<(u64, RecordedInput)>,
+}
+
+impl Replay {
+    pub fn new(
+        pack: Option<String>,
+        level: String,
+        daily: Option<i64>,
+        start: Board,
+    ) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            pack,
+            level,
+            daily,
+            start,
+            frames: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Tack on the next frame's input.
+    pub fn record(&mut self, input: &Input) {
+        let input = RecordedInput::from(input);
+        if self.events.last().map(|(_, last)| last) != Some(&input) {
+            self.events.push((self.frames, input));
+        }
+        self.frames += 1;
+    }
+
+    /// Every frame's input, ready to be fed back in.
+    pub fn script(&self) -> ScriptedInput {
+        let mut script = ScriptedInput::new();
+        let mut events = self.events.iter().peekable();
+        let mut current = Input::default();
+        for frame in 0..self.frames {
+            if let Some((_, input)) = events.next_if(|(at, _)| *at == frame) {
+                current = input.into();
+            }
+            script = script.frame(current.clone());
+        }
+        script
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> eyre::Result<Self> {
+        let src = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("couldn't read {}", path))?;
+        let replay: Replay =
+            json5::from_str(&src).wrap_err("malformed replay")?;
+        if replay.version != REPLAY_VERSION {
+            eyre::bail!(
+                "replay is version {}, but we only know how to play version {}",
+                replay.version,
+                REPLAY_VERSION
+            );
+        }
+        Ok(replay)
+    }
+
+    /// Keep this as the last attempt at its level.
+    #[cfg(not(any(target_arch = "wasm32", test)))]
+    pub fn store(&self) {
+        use crate::save::{FileStorage, SaveStorage};
+
+        let name = format!(
+            "replays/{}-{}.json5",
+            self.pack.as_deref().unwrap_or("main"),
+            self.level
+        );
+        let Some(mut storage) = FileStorage::new(&name) else {
+            return;
+        };
+        let res = json5::to_string(self)
+            .wrap_err("couldn't serialize replay")
+            .and_then(|s| storage.store(&s));
+        if let Err(e) = res {
+            warn!("couldn't write replay: {:?}", e);
+        }
+    }
+
+    /// Tests don't get to write over the player's replays.
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn store(&self) {}
+}
+
+/// The replay being recorded right now, if any. It's shared with the panic
+/// hook, so a crash still leaves a replay of how it happened.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder(Arc<Mutex<Option<Replay>>>);
+
+impl Recorder {
+    pub fn start(&self, replay: Replay) {
+        *self.lock() = Some(replay);
+    }
+
+    /// Tack on the next frame's input, writing the whole thing out every
+    /// so often.
+    pub fn record(&self, input: &Input) {
+        if let Some(replay) = &mut *self.lock() {
+            replay.record(input);
+            if replay.frames % STORE_EVERY_FRAMES == 0 {
+                replay.store();
+            }
+        }
+    }
+
+    /// Write out what's been recorded so far.
+    pub fn store(&self) {
+        if let Some(replay) = &*self.lock() {
+            replay.store();
+        }
+    }
+
+    /// Stop recording, handing back what there was. This doesn't write it
+    /// out.
+    pub fn stop(&self) -> Option<Replay> {
+        self.lock().take()
+    }
+
+    /// Write out the recording before the panic message goes out.
+    pub fn store_on_panic(&self) {
+        let this = self.clone();
+        let prev = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // Don't wait on the lock if it was the one holding it that
+            // panicked
+            if let Ok(replay) = this.0.try_lock() {
+                if let Some(replay) = &*replay {
+                    replay.store();
+                }
+            }
+            prev(info);
+        }));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Replay>> {
+        // A panic halfway through recording a frame doesn't make the rest
+        // of it any less worth keeping
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// [`Input`], but in a form that can be written down.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInput {
+    mouse: (f32, f32),
+    #[serde(default)]
+    mouse_down: bool,
+    #[serde(default)]
+    mouse_pressed: bool,
+    #[serde(default)]
+    right_pressed: bool,
+    #[serde(default)]
+    touches: Vec<RecordedTouch>,
+    #[serde(default)]
+    actions: BitFlags<Action>,
+    #[serde(default)]
+    walk: Option<(Direction, bool)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedTouch {
+    id: u64,
+    #[serde(with = "TouchPhaseDef")]
+    phase: TouchPhase,
+    position: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TouchPhase")]
+enum TouchPhaseDef {
+    Started,
+    Stationary,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+impl From<&Input> for RecordedInput {
+    fn from(input: &Input) -> Self {
+        Self {
+            mouse: input.mouse.into(),
+            mouse_down: input.mouse_down,
+            mouse_pressed: input.mouse_pressed,
+            right_pressed: input.right_pressed,
+            touches: input
+                .touches
+                .iter()
+                .map(|t| RecordedTouch {
+                    id: t.id,
+                    phase: t.phase,
+                    position: t.position.into(),
+                })
+                .collect(),
+            actions: input.actions,
+            walk: input.walk.map(|walk| (walk.dir, walk.draw)),
+        }
+    }
+}
+
+impl From<&RecordedInput> for Input {
+    fn from(input: &RecordedInput) -> Self {
+        Self {
+            mouse: input.mouse.into(),
+            mouse_down: input.mouse_down,
+            mouse_pressed: input.mouse_pressed,
+            right_pressed: input.right_pressed,
+            touches: input
+                .touches
+                .iter()
+                .map(|t| Touch {
+                    id: t.id,
+                    phase: t.phase,
+                    position: t.position.into(),
+                })
+                .collect(),
+            actions: input.actions,
+            walk: input.walk.map(|(dir, draw)| Walk { dir, draw }),
+        }
+    }
+}
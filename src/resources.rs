@@ -1,62 +1,210 @@
 use std::{
-    ops::Deref,
-    sync::{Mutex, MutexGuard},
+    cell::OnceCell,
+    future::{poll_fn, Future},
+    panic::catch_unwind,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
 };
 
 use macroquad::{
-    audio::{load_sound, Sound},
+    audio::{load_sound_from_bytes, Sound},
     prelude::*,
 };
 
-use crate::puzzle::{Level, Puzzle};
+use ahash::{AHashMap, AHashSet};
+use eyre::{bail, eyre};
+use serde::{de::DeserializeOwned, Deserialize};
 
+use crate::{
+    config::Config,
+    cutscene::Cutscenes,
+    generate,
+    locale::{Locale, Locales},
+    music::Track,
+    puzzle::Level,
+    replay::Recorder,
+    save::Save,
+    sfx::{SoundEvent, SoundQueue},
+    util::hexcolor,
+};
+
+/// Everything the states get to look at and change, handed to them each
+/// frame.
 pub struct Resources {
     pub textures: Textures,
-    pub sounds: Sounds,
     pub levels: Levels,
+    /// Extra levels from `levelpacks/`
+    pub packs: Vec<LevelPack>,
+    /// Packs that didn't load, so whoever made them can see why
+    pub broken_packs: Vec<LoadError>,
+    pub cutscenes: Cutscenes,
+    pub locales: Locales,
+    /// Daily puzzles made so far, by day; see [`Resources::daily`]
+    pub dailies: AHashMap<i64, Level>,
+
+    pub save: Save,
+    pub config: Config,
+    /// Sounds to play once the update is done
+    pub sfx: SoundQueue,
+    /// The attempt at a level that's being recorded, if there is one
+    pub recorder: Recorder,
+}
+
+/// Sounds and music. Only the main loop plays anything, so the states never
+/// see these.
+pub struct Audio {
+    pub sounds: Sounds,
+    pub music: MusicTracks,
 }
 
 impl Resources {
-    pub async fn init() {
-        let textures = Textures::init().await;
+    /// Load everything, or return every file that went wrong.
+    ///
+    /// `progress` gets updated as files come in, for the loading screen.
+    pub async fn load(
+        save: Save,
+        config: Config,
+        progress: Arc<Mutex<LoadProgress>>,
+    ) -> Result<(Resources, Audio), Vec<LoadError>> {
+        let mut loader = Loader::new(progress);
+        // Ask for everything up front so it all downloads at once on the
+        // web. Things still get waited on one by one below.
+        for name in Textures::NAMES {
+            loader.start(&texture_path(name));
+        }
+        for name in Sounds::NAMES {
+            loader.start(&sound_path(name));
+        }
+        for path in ["music.json5", "puzzles.json5", "cutscenes.json5"] {
+            loader.start(path);
+        }
+        for code in LOCALES {
+            loader.start(&locale_path(code));
+        }
+
+        let textures = Textures::init(&mut loader).await;
 
-        let sounds = Sounds::init().await;
+        let sounds = Sounds::init(&mut loader).await;
+        let music = MusicTracks::init(&mut loader).await;
 
-        let levels = Levels::init().await;
+        let levels = Levels::init(&mut loader).await;
+        let cutscenes = loader.json5("cutscenes.json5").await;
+        let locales =
+            locales(&mut loader, config.data.language.as_deref()).await;
+        let (packs, broken_packs) = level_packs(&mut loader).await;
 
         // coroutines::wait_seconds(5.0).await;
 
-        let mut lock = THE_RESOURCES.lock().unwrap();
-        *lock = Some(Resources {
+        let (
+            Some(textures),
+            Some(sounds),
+            Some(music),
+            Some(levels),
+            Some(cutscenes),
+            Some(locales),
+        ) = (textures, sounds, music, levels, cutscenes, locales)
+        else {
+            return Err(loader.errors);
+        };
+
+        let res = Resources {
             textures,
-            sounds,
             levels,
-        });
+            packs,
+            broken_packs,
+            cutscenes,
+            locales,
+            dailies: AHashMap::new(),
+            save,
+            config,
+            sfx: SoundQueue::default(),
+            recorder: Recorder::default(),
+        };
+        Ok((res, Audio { sounds, music }))
     }
 
-    pub fn get() -> ResourcesRef {
-        let lock = THE_RESOURCES.lock().expect("resources mutex was locked :(");
-        ResourcesRef(lock)
+    /// Built-in levels and text, but blank textures and nothing on disk,
+    /// so states can be run without a window.
+    #[cfg(test)]
+    pub fn stub() -> Self {
+        use crate::save::MemoryStorage;
+
+        fn read<T: DeserializeOwned>(path: &str) -> T {
+            let path = format!("{}/{}", RESOURCES_ROOT, path);
+            json5::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        }
+        let locales = LOCALES
+            .iter()
+            .map(|code| (code.to_string(), read(&locale_path(code))))
+            .collect();
+
+        Resources {
+            textures: Textures::stub(),
+            levels: Levels::new(read("puzzles.json5")),
+            packs: Vec::new(),
+            broken_packs: Vec::new(),
+            cutscenes: read("cutscenes.json5"),
+            locales: Locales::new(locales),
+            dailies: AHashMap::new(),
+            save: Save::load(Box::<MemoryStorage>::default()),
+            config: Config::load(Box::<MemoryStorage>::default()),
+            sfx: SoundQueue::default(),
+            recorder: Recorder::default(),
+        }
     }
-}
 
-#[cfg(debug_assertions)]
-const RESOURCES_ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources");
-#[cfg(not(debug_assertions))]
-const RESOURCES_ROOT: &str = "./resources";
+    /// The puzzle for the day, counted since 1970-01-01. It's made the
+    /// first time it's asked for.
+    pub fn daily(&mut self, day: i64) -> &Level {
+        self.dailies
+            .entry(day)
+            .or_insert_with(|| generate::daily(day))
+    }
 
-static THE_RESOURCES: Mutex<Option<Resources>> = Mutex::new(None);
+    /// Levels from the given index into `packs`, or the built-in ones.
+    pub fn pack_levels(&self, pack: Option<usize>) -> &Levels {
+        match pack {
+            Some(idx) => &self.packs[idx].levels,
+            None => &self.levels,
+        }
+    }
 
-pub struct ResourcesRef(MutexGuard<'static, Option<Resources>>);
+    /// Is there anything to show in the pack picker, even if it's only
+    /// broken packs?
+    pub fn any_packs(&self) -> bool {
+        !self.packs.is_empty() || !self.broken_packs.is_empty()
+    }
 
-impl Deref for ResourcesRef {
-    type Target = Resources;
+    /// What to save progress in the pack under.
+    pub fn pack_id(&self, pack: Option<usize>) -> Option<&str> {
+        pack.map(|idx| self.packs[idx].id.as_str())
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.0.as_ref().expect("assets must be filled")
+    pub fn level_description<'a>(
+        &'a self,
+        pack: Option<usize>,
+        id: &'a str,
+    ) -> &'a str {
+        match pack {
+            Some(idx) => self.packs[idx]
+                .descriptions
+                .get(id)
+                .map_or("", String::as_str),
+            None => self.locales.level_description(id),
+        }
     }
 }
 
+#[cfg(debug_assertions)]
+pub const RESOURCES_ROOT: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/resources");
+#[cfg(not(debug_assertions))]
+pub const RESOURCES_ROOT: &str = "./resources";
+
+/// Language codes to load from `locales/`. The first one is the fallback.
+const LOCALES: &[&str] = &["en", "fr"];
+
 pub struct Textures {
     pub wheat_hex: Texture2D,
     pub background: Texture2D,
@@ -66,45 +214,223 @@ pub struct Textures {
     pub font: Texture2D,
     pub buttons: Texture2D,
     pub billboard: Texture2D,
+    pub level_select_buttons: Texture2D,
 
     pub ufo: Texture2D,
+    pub portraits: Texture2D,
 }
 
 impl Textures {
-    async fn init() -> Self {
+    /// File names, in the same order as the fields
+    const NAMES: [&'static str; 10] = [
+        "wheat_hex",
+        "background",
+        "path",
+        "numbers",
+        "font",
+        "buttons",
+        "billboard",
+        "level_select_buttons",
+        "ufo",
+        "portraits",
+    ];
+
+    async fn init(loader: &mut Loader) -> Option<Self> {
+        let mut loaded = Vec::new();
+        for name in Self::NAMES {
+            loaded.push(loader.texture(&texture_path(name)).await);
+        }
+        let [wheat_hex, background, paths, numbers, font, buttons, billboard, level_select_buttons, ufo, portraits] =
+            loaded.try_into().ok()?;
+
+        Some(Self {
+            wheat_hex: wheat_hex?,
+            background: background?,
+            paths: paths?,
+            numbers: numbers?,
+            font: font?,
+            buttons: buttons?,
+            billboard: billboard?,
+            level_select_buttons: level_select_buttons?,
+            ufo: ufo?,
+            portraits: portraits?,
+        })
+    }
+
+    #[cfg(test)]
+    fn stub() -> Self {
+        let empty = Texture2D::empty;
         Self {
-            wheat_hex: texture("wheat_hex").await,
-            background: texture("background").await,
-            paths: texture("path").await,
-            numbers: texture("numbers").await,
-            font: texture("font").await,
-            buttons: texture("buttons").await,
-            billboard: texture("billboard").await,
-            ufo: texture("ufo").await,
+            wheat_hex: empty(),
+            background: empty(),
+            paths: empty(),
+            numbers: empty(),
+            font: empty(),
+            buttons: empty(),
+            billboard: empty(),
+            level_select_buttons: empty(),
+            ufo: empty(),
+            portraits: empty(),
         }
     }
+
+    /// Look up a texture by the file name it was loaded from.
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    pub fn by_name_mut(&mut self, name: &str) -> Option<&mut Texture2D> {
+        Some(match name {
+            "wheat_hex" => &mut self.wheat_hex,
+            "background" => &mut self.background,
+            "path" => &mut self.paths,
+            "numbers" => &mut self.numbers,
+            "font" => &mut self.font,
+            "buttons" => &mut self.buttons,
+            "billboard" => &mut self.billboard,
+            "level_select_buttons" => &mut self.level_select_buttons,
+            "ufo" => &mut self.ufo,
+            "portraits" => &mut self.portraits,
+            _ => return None,
+        })
+    }
 }
 
-pub struct Sounds {}
+pub struct Sounds {
+    pub edge_on: Sound,
+    pub edge_off: Sound,
+    pub check_failed: Sound,
+    pub check_passed: Sound,
+    pub button_hover: Sound,
+    pub button_click: Sound,
+    pub text_blip: Sound,
+}
 
 impl Sounds {
-    async fn init() -> Self {
-        Self {}
+    /// File names, in the same order as the fields
+    const NAMES: [&'static str; 7] = [
+        "edge_on",
+        "edge_off",
+        "check_failed",
+        "check_passed",
+        "button_hover",
+        "button_click",
+        "text_blip",
+    ];
+
+    async fn init(loader: &mut Loader) -> Option<Self> {
+        let mut loaded = Vec::new();
+        for name in Self::NAMES {
+            loaded.push(loader.sound(&sound_path(name)).await);
+        }
+        let [edge_on, edge_off, check_failed, check_passed, button_hover, button_click, text_blip] =
+            loaded.try_into().ok()?;
+
+        Some(Self {
+            edge_on: edge_on?,
+            edge_off: edge_off?,
+            check_failed: check_failed?,
+            check_passed: check_passed?,
+            button_hover: button_hover?,
+            button_click: button_click?,
+            text_blip: text_blip?,
+        })
+    }
+
+    pub fn get(&self, event: SoundEvent) -> Sound {
+        match event {
+            SoundEvent::EdgeOn => self.edge_on,
+            SoundEvent::EdgeOff => self.edge_off,
+            SoundEvent::CheckFailed => self.check_failed,
+            SoundEvent::CheckPassed => self.check_passed,
+            SoundEvent::ButtonHover => self.button_hover,
+            SoundEvent::ButtonClick => self.button_click,
+            SoundEvent::TextBlip => self.text_blip,
+        }
+    }
+}
+
+pub struct MusicTracks {
+    pub menu: Sound,
+    /// One for each row of levels
+    pub rows: Vec<Sound>,
+}
+
+#[derive(Deserialize)]
+struct MusicManifest {
+    menu: String,
+    rows: Vec<String>,
+}
+
+impl MusicTracks {
+    async fn init(loader: &mut Loader) -> Option<Self> {
+        let manifest: MusicManifest = loader.json5("music.json5").await?;
+        let names = || std::iter::once(&manifest.menu).chain(&manifest.rows);
+        for name in names() {
+            loader.start(&music_path(name));
+        }
+
+        // Rows can share tracks, so only load each one once
+        let mut loaded = AHashMap::new();
+        for name in names() {
+            if !loaded.contains_key(name) {
+                let sound = loader.sound(&music_path(name)).await;
+                loaded.insert(name.clone(), sound);
+            }
+        }
+
+        Some(Self {
+            menu: loaded[&manifest.menu]?,
+            rows: manifest
+                .rows
+                .iter()
+                .map(|name| loaded[name])
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn get(&self, track: Track) -> Sound {
+        match track {
+            Track::Menu => self.menu,
+            Track::Row(row) => self
+                .rows
+                .get(row)
+                .or(self.rows.last())
+                .copied()
+                .unwrap_or(self.menu),
+        }
     }
 }
 
 pub struct Levels {
     pub rows: Vec<Vec<Level>>,
+    /// Whether the solver could crack each level, worked out the first time
+    /// anyone asks
+    solvable: OnceCell<Vec<Vec<bool>>>,
 }
 
 impl Levels {
-    async fn init() -> Self {
-        let file = load_string(&format!("{}/puzzles.json5", RESOURCES_ROOT))
-            .await
-            .unwrap();
-        let rows = json5::from_str(&file).unwrap();
+    pub fn new(rows: Vec<Vec<Level>>) -> Self {
+        Self {
+            rows,
+            solvable: OnceCell::new(),
+        }
+    }
+
+    async fn init(loader: &mut Loader) -> Option<Self> {
+        let rows = loader.json5("puzzles.json5").await?;
+        Some(Self::new(rows))
+    }
 
-        Self { rows }
+    /// Can the solver find a way through the level? Ones it can't shouldn't
+    /// hold up the rest.
+    pub fn is_solvable(&self, row: usize, col: usize) -> bool {
+        let solvable = self.solvable.get_or_init(|| {
+            self.rows
+                .iter()
+                .map(|levels| {
+                    levels.iter().map(|l| l.puzzle.solve().is_some()).collect()
+                })
+                .collect()
+        });
+        solvable[row][col]
     }
 
     pub fn get(&self, row: usize, col: usize) -> Option<&Level> {
@@ -112,17 +438,466 @@ impl Levels {
     }
 }
 
-async fn texture(path: &str) -> Texture2D {
-    let tex =
-        load_texture(&format!("{}/textures/{}.png", RESOURCES_ROOT, path))
-            .await
-            .unwrap();
+/// `language` is the code of the one to start out in.
+async fn locales(
+    loader: &mut Loader,
+    language: Option<&str>,
+) -> Option<Locales> {
+    let mut locales = Vec::new();
+    for code in LOCALES {
+        let locale: Option<Locale> = loader.json5(&locale_path(code)).await;
+        locales.push((code.to_string(), locale));
+    }
+    let locales = locales
+        .into_iter()
+        .map(|(code, locale)| Some((code, locale?)))
+        .collect::<Option<_>>()?;
+
+    let mut locales = Locales::new(locales);
+    if let Some(code) = language {
+        if !locales.set_current(code) {
+            warn!("don't know the language {:?}, using the default", code);
+        }
+    }
+    Some(locales)
+}
+
+/// A set of levels someone dropped in `levelpacks/`, as `<id>.json5`.
+pub struct LevelPack {
+    pub id: String,
+    pub name: String,
+    pub author: String,
+    pub levels: Levels,
+    /// Replaces the usual background color
+    pub tint: Option<Color>,
+    /// Level descriptions by level id. Packs don't get translated.
+    pub descriptions: AHashMap<String, String>,
+}
+
+/// What's in a pack's file. `docs/example-pack.json5` goes through all of
+/// it.
+#[derive(Deserialize)]
+struct PackManifest {
+    name: String,
+    #[serde(default)]
+    author: String,
+    /// Laid out like `puzzles.json5`
+    rows: Vec<Vec<Level>>,
+    /// Like `0x8bb392ff`
+    #[serde(default)]
+    tint: Option<u32>,
+    #[serde(default)]
+    descriptions: AHashMap<String, String>,
+}
+
+/// Packs are optional, so a broken one gets set aside with what's wrong
+/// with it instead of stopping the whole game.
+async fn level_packs(loader: &mut Loader) -> (Vec<LevelPack>, Vec<LoadError>) {
+    let ids = pack_ids();
+    for id in &ids {
+        loader.start(&pack_path(id));
+    }
+
+    let mut packs = Vec::new();
+    let mut broken = Vec::new();
+    for id in ids {
+        let errors_before = loader.errors.len();
+        let manifest: Option<PackManifest> =
+            loader.json5(&pack_path(&id)).await;
+        let manifest = manifest.filter(|it| match check_pack(&it.rows) {
+            Ok(()) => true,
+            Err(e) => {
+                let suggestion = "Compare the level with the ones in \
+                                  puzzles.json5";
+                loader.fail(&pack_path(&id), None, e, suggestion.to_owned());
+                false
+            }
+        });
+        broken.extend(loader.errors.split_off(errors_before));
+        let Some(manifest) = manifest else {
+            continue;
+        };
+        packs.push(LevelPack {
+            id,
+            name: manifest.name,
+            author: manifest.author,
+            levels: Levels::new(manifest.rows),
+            tint: manifest.tint.map(hexcolor),
+            descriptions: manifest.descriptions,
+        });
+    }
+    (packs, broken)
+}
+
+/// Any bigger and the marks run off the top of the screen.
+const MAX_PACK_RADIUS: u32 = 2;
+
+/// The checker takes a puzzle's shape on trust, so anything that didn't
+/// ship with the game gets looked over first.
+fn check_pack(rows: &[Vec<Level>]) -> eyre::Result<()> {
+    let mut ids = AHashSet::new();
+    for level in rows.iter().flatten() {
+        if !ids.insert(&level.id) {
+            bail!("there's more than one level called {}", level.id);
+        }
+        let radius = level.puzzle.radius();
+        if !(1..=MAX_PACK_RADIUS).contains(&radius) {
+            bail!(
+                "{} has radius {}, but it needs to be from 1 to {}",
+                level.id,
+                radius,
+                MAX_PACK_RADIUS
+            );
+        }
+        let lines = radius as usize * 2 + 1;
+        for (flank, marks) in level.puzzle.marks().into_iter().enumerate() {
+            if marks.len() != lines {
+                bail!(
+                    "{} has {} lines of marks on flank {}, but radius {} \
+                     needs {}",
+                    level.id,
+                    marks.len(),
+                    flank + 1,
+                    radius,
+                    lines
+                );
+            }
+            for (i, line) in marks.iter().enumerate() {
+                let cells = lines - i.abs_diff(radius as usize);
+                if line.len() > cells {
+                    bail!(
+                        "{} has {} marks on line {} of flank {}, which is \
+                         only {} cells long",
+                        level.id,
+                        line.len(),
+                        i + 1,
+                        flank + 1,
+                        cells
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Everything in `levelpacks/`, sorted.
+#[cfg(not(target_arch = "wasm32"))]
+fn pack_ids() -> Vec<String> {
+    let dir = match std::fs::read_dir(format!("{}/levelpacks", RESOURCES_ROOT))
+    {
+        Ok(it) => it,
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("couldn't look for level packs: {}", e);
+            }
+            return Vec::new();
+        }
+    };
+    let mut ids = dir
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()?.to_str()? != "json5" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_owned())
+        })
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+/// There's no listing a directory over the web.
+#[cfg(target_arch = "wasm32")]
+fn pack_ids() -> Vec<String> {
+    Vec::new()
+}
+
+/// A file that couldn't be loaded.
+pub struct LoadError {
+    /// Relative to the resources folder
+    pub path: String,
+    /// Line and column, for files that are there but don't parse
+    pub location: Option<(usize, usize)>,
+    pub error: eyre::Report,
+    /// What to try to fix it
+    pub suggestion: String,
+}
+
+/// How far along `Resources::load` is, for the loading screen.
+#[derive(Debug, Clone, Default)]
+pub struct LoadProgress {
+    pub done: usize,
+    pub total: usize,
+    /// The file being waited on
+    pub current: String,
+}
+
+type Fetch = Pin<Box<dyn Future<Output = Result<Vec<u8>, FileError>> + Send>>;
+
+/// Keeps going after something fails to load, so everything wrong can be
+/// reported at once.
+struct Loader {
+    errors: Vec<LoadError>,
+    /// Files that have been asked for but not used yet, by path
+    fetches: AHashMap<String, Fetch>,
+    /// Files that have arrived but not been used yet
+    fetched: AHashMap<String, Result<Vec<u8>, FileError>>,
+    /// When the loading screen last got a chance to draw
+    last_yield: f64,
+    /// Shared with the loading screen
+    progress: Arc<Mutex<LoadProgress>>,
+}
+
+impl Loader {
+    fn new(progress: Arc<Mutex<LoadProgress>>) -> Self {
+        Self {
+            errors: Vec::new(),
+            fetches: AHashMap::new(),
+            fetched: AHashMap::new(),
+            last_yield: 0.0,
+            progress,
+        }
+    }
+
+    fn progress(&self) -> std::sync::MutexGuard<'_, LoadProgress> {
+        self.progress.lock().expect("progress mutex was poisoned")
+    }
+
+    /// Start downloading a file, if it isn't already.
+    fn start(&mut self, path: &str) {
+        if self.fetches.contains_key(path) || self.fetched.contains_key(path) {
+            return;
+        }
+        let full = format!("{}/{}", RESOURCES_ROOT, path);
+        let fetch = Box::pin(async move { load_file(&full).await });
+        self.fetches.insert(path.to_owned(), fetch);
+        self.progress().total += 1;
+    }
+
+    /// Push every download along, and wait for this one to be done.
+    async fn fetch(&mut self, path: &str) -> Result<Vec<u8>, FileError> {
+        self.start(path);
+        self.progress().current = path.to_owned();
+        let bytes = poll_fn(|cx| {
+            let fetched = &mut self.fetched;
+            self.fetches
+                .retain(|path, fetch| match fetch.as_mut().poll(cx) {
+                    Poll::Ready(bytes) => {
+                        fetched.insert(path.clone(), bytes);
+                        false
+                    }
+                    Poll::Pending => true,
+                });
+            match self.fetched.remove(path) {
+                Some(bytes) => Poll::Ready(bytes),
+                None => Poll::Pending,
+            }
+        })
+        .await;
+        self.progress().done += 1;
+
+        // Locally everything's ready straight away, so make a point of
+        // letting the progress bar move
+        if get_time() - self.last_yield > 1.0 / 30.0 {
+            next_frame().await;
+            self.last_yield = get_time();
+        }
+        bytes
+    }
+
+    fn fail(
+        &mut self,
+        path: &str,
+        location: Option<(usize, usize)>,
+        error: eyre::Report,
+        suggestion: String,
+    ) {
+        error!("couldn't load {}: {:?}", path, error);
+        self.errors.push(LoadError {
+            path: path.to_owned(),
+            location,
+            error,
+            suggestion,
+        });
+    }
+
+    async fn bytes(&mut self, path: &str) -> Option<Vec<u8>> {
+        match self.fetch(path).await {
+            Ok(it) => Some(it),
+            Err(e) => {
+                let suggestion = format!(
+                    "Check the file exists. Resources are read from {}",
+                    RESOURCES_ROOT
+                );
+                self.fail(path, None, e.into(), suggestion);
+                None
+            }
+        }
+    }
+
+    async fn json5<T: DeserializeOwned>(&mut self, path: &str) -> Option<T> {
+        let bytes = self.bytes(path).await?;
+        let src = match String::from_utf8(bytes) {
+            Ok(it) => it,
+            Err(e) => {
+                let suggestion = "Save it as UTF-8".to_owned();
+                self.fail(path, None, e.into(), suggestion);
+                return None;
+            }
+        };
+        match json5::from_str(&src) {
+            Ok(it) => Some(it),
+            Err(json5::Error::Message { msg, location }) => {
+                let (location, error, suggestion) = match location {
+                    // Syntax errors come with a big drawing of the line; the
+                    // gist is at the end
+                    Some(loc) => (
+                        Some((loc.line, loc.column)),
+                        msg.rsplit("= ").next().unwrap_or(&msg).to_owned(),
+                        "Look for a missing comma, quote or bracket near there",
+                    ),
+                    None => (
+                        None,
+                        msg,
+                        "It's valid JSON5 but not the right shape. Compare it \
+                         with the other entries",
+                    ),
+                };
+                self.fail(path, location, eyre!(error), suggestion.to_owned());
+                None
+            }
+        }
+    }
+
+    async fn texture(&mut self, path: &str) -> Option<Texture2D> {
+        let bytes = self.bytes(path).await?;
+        match decode_texture(&bytes) {
+            Ok(it) => Some(it),
+            Err(e) => {
+                let suggestion =
+                    "Make sure it's a PNG an image editor can open".to_owned();
+                self.fail(path, None, e, suggestion);
+                None
+            }
+        }
+    }
+
+    async fn sound(&mut self, path: &str) -> Option<Sound> {
+        let bytes = self.bytes(path).await?;
+        match load_sound_from_bytes(&bytes).await {
+            Ok(it) => Some(it),
+            Err(e) => {
+                let suggestion = "Make sure it's a WAV file".to_owned();
+                self.fail(path, None, e.into(), suggestion);
+                None
+            }
+        }
+    }
+}
+
+fn texture_path(name: &str) -> String {
+    format!("textures/{}.png", name)
+}
+
+fn sound_path(name: &str) -> String {
+    format!("sounds/{}.wav", name)
+}
+
+fn music_path(name: &str) -> String {
+    format!("music/{}.wav", name)
+}
+
+fn locale_path(code: &str) -> String {
+    format!("locales/{}.json5", code)
+}
+
+fn pack_path(id: &str) -> String {
+    format!("levelpacks/{}.json5", id)
+}
+
+/// Make a pixel-art texture from PNG bytes.
+pub fn decode_texture(bytes: &[u8]) -> eyre::Result<Texture2D> {
+    // Macroquad panics on images it can't read
+    let image = catch_unwind(|| Image::from_file_with_format(bytes, None))
+        .map_err(|_| eyre!("not a valid image"))?;
+    let tex = Texture2D::from_image(&image);
     tex.set_filter(FilterMode::Nearest);
-    tex
+    Ok(tex)
 }
 
-async fn sound(path: &str) -> Sound {
-    load_sound(&format!("{}/sounds/{}.ogg", RESOURCES_ROOT, path))
-        .await
-        .unwrap()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cutscene::Step;
+
+    fn pack_errors(levels: &str) -> String {
+        let src = format!("{{ name: 'Test', rows: [[ {} ]] }}", levels);
+        let manifest: PackManifest = json5::from_str(&src).unwrap();
+        match check_pack(&manifest.rows) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn built_in_levels_would_pass_as_a_pack() {
+        let res = Resources::stub();
+        check_pack(&res.levels.rows).unwrap();
+    }
+
+    #[test]
+    fn example_pack_is_fine() {
+        let src = include_str!("../docs/example-pack.json5");
+        let manifest: PackManifest = json5::from_str(src).unwrap();
+        check_pack(&manifest.rows).unwrap();
+    }
+
+    #[test]
+    fn malformed_packs_are_set_aside() {
+        let fine = "{ id: 'a', puzzle: { radius: 1, \
+                    marks: [[[], [2, 2], []], [[], [], []], [[], [], []]] } }";
+        assert_eq!(pack_errors(fine), "");
+        let twice = format!("{}, {}", fine, fine);
+        assert!(pack_errors(&twice).contains("more than one"));
+        let flat = "{ id: 'a', puzzle: { radius: 0, \
+                    marks: [[[]], [[]], [[]]] } }";
+        assert!(pack_errors(flat).contains("radius 0"));
+        let short = "{ id: 'a', puzzle: { radius: 2, \
+                     marks: [[[], [], []], [[], [], []], [[], [], []]] } }";
+        assert!(pack_errors(short).contains("3 lines of marks on flank 1"));
+        let long = "{ id: 'a', puzzle: { radius: 1, marks: [\
+                    [[], [], []], [[1, 1, 1], [], []], [[], [], []]] } }";
+        assert!(pack_errors(long).contains("line 1 of flank 2"));
+    }
+
+    /// Rather than quietly falling back to the first language.
+    #[test]
+    fn cutscenes_are_translated() {
+        let mut res = Resources::stub();
+        for code in LOCALES {
+            assert!(res.locales.set_current(code));
+            let locale = res.locales.current();
+            for speaker in res.cutscenes.speakers.values() {
+                let name = &speaker.name;
+                assert!(
+                    locale.speakers.contains_key(name),
+                    "{} {}",
+                    code,
+                    name
+                );
+            }
+            for step in res.cutscenes.scenes.iter().flat_map(|s| &s.steps) {
+                if let Step::Say { line, .. } = step {
+                    assert!(
+                        locale.dialogue.contains_key(line),
+                        "{} {}",
+                        code,
+                        line
+                    );
+                }
+            }
+        }
+    }
 }
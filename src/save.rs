@@ -0,0 +1,432 @@
+//! Remembering progress between runs.
+
+use std::collections::BTreeMap;
+
+use eyre::WrapErr;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{generate::iso_date, puzzle::Board};
+
+/// Bump this whenever the layout of [`SaveData`] changes, and teach
+/// [`Save::read`] to bring older saves up to date.
+pub const SAVE_VERSION: u32 = 2;
+
+/// Somewhere to stash the save file.
+///
+/// Desktop builds write to the platform data directory; other backends
+/// (like browser local storage) can be slotted in later.
+pub trait SaveStorage: Send {
+    /// Return `Ok(None)` if nothing has been saved yet.
+    fn load(&self) -> eyre::Result<Option<String>>;
+
+    fn store(&mut self, data: &str) -> eyre::Result<()>;
+
+    /// Copy whatever's stored off to the side, so it survives being
+    /// written over.
+    fn back_up(&mut self) -> eyre::Result<()>;
+}
+
+/// Writes the save to a file in the platform data directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    path: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    /// Returns `None` if the OS won't tell us where to put things.
+    pub fn new(filename: &str) -> Option<Self> {
+        let dirs =
+            directories::ProjectDirs::from("", "gamma-delta", "crop-circles")?;
+        Some(Self {
+            path: dirs.data_dir().join(filename),
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn load(&self) -> eyre::Result<Option<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(s) => Ok(Some(s)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).wrap_err_with(|| {
+                format!("couldn't read {}", self.path.display())
+            }),
+        }
+    }
+
+    fn store(&mut self, data: &str) -> eyre::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).wrap_err_with(|| {
+                format!("couldn't create {}", parent.display())
+            })?;
+        }
+        // Write to the side and then move it over so a crash halfway
+        // through doesn't eat the old save
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, data)
+            .wrap_err_with(|| format!("couldn't write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path).wrap_err_with(|| {
+            format!("couldn't move save into {}", self.path.display())
+        })
+    }
+
+    fn back_up(&mut self) -> eyre::Result<()> {
+        let mut bak = self.path.clone().into_os_string();
+        bak.push(".bak");
+        std::fs::copy(&self.path, &bak).wrap_err_with(|| {
+            format!("couldn't back up {}", self.path.display())
+        })?;
+        Ok(())
+    }
+}
+
+/// Keeps the save in memory only. Forgets everything on exit.
+#[derive(Default)]
+pub struct MemoryStorage(Option<String>);
+
+impl SaveStorage for MemoryStorage {
+    fn load(&self) -> eyre::Result<Option<String>> {
+        Ok(self.0.clone())
+    }
+
+    fn store(&mut self, data: &str) -> eyre::Result<()> {
+        self.0 = Some(data.to_owned());
+        Ok(())
+    }
+
+    fn back_up(&mut self) -> eyre::Result<()> {
+        // It's all gone on exit anyway
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    /// Indexed the same way as `Levels::rows`.
+    #[serde(default)]
+    pub levels: Vec<Vec<LevelProgress>>,
+    /// Progress through level packs, by pack id
+    #[serde(default)]
+    pub packs: BTreeMap<String, Vec<Vec<LevelProgress>>>,
+    /// Daily puzzles, by date like `2023-01-07`
+    #[serde(default)]
+    pub dailies: BTreeMap<String, LevelProgress>,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            levels: Vec::new(),
+            packs: BTreeMap::new(),
+            dailies: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LevelProgress {
+    #[serde(default)]
+    pub solved: bool,
+    /// Fewest frames it's taken to solve this
+    #[serde(default)]
+    pub best_time: Option<u64>,
+    /// Fewest edge toggles it's taken to solve this
+    #[serde(default)]
+    pub best_moves: Option<u32>,
+    /// Has it been solved within the level's par in one go?
+    #[serde(default)]
+    pub under_par: bool,
+    /// The board as the player last left it
+    #[serde(default)]
+    pub board: Option<Board>,
+    /// Frames spent on `board` so far, so coming back to it doesn't
+    /// restart the clock
+    #[serde(default)]
+    pub frames: u64,
+    /// Moves made on `board` so far
+    #[serde(default)]
+    pub moves: u32,
+}
+
+impl LevelProgress {
+    /// Mark the level as solved and keep the better of the old and new
+    /// records.
+    ///
+    /// Returns whether either record got beaten.
+    pub fn record_solve(
+        &mut self,
+        frames: u64,
+        moves: u32,
+        under_par: bool,
+    ) -> bool {
+        // The first solve doesn't beat anything
+        let new_best = self.solved
+            && (self.best_time.is_some_and(|t| frames < t)
+                || self.best_moves.is_some_and(|m| moves < m));
+        self.solved = true;
+        self.best_time = Some(self.best_time.map_or(frames, |t| t.min(frames)));
+        self.best_moves = Some(self.best_moves.map_or(moves, |m| m.min(moves)));
+        self.under_par |= under_par;
+        new_best
+    }
+}
+
+/// Just enough of the save to figure out what version it is.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+pub struct Save {
+    pub data: SaveData,
+    storage: Box<dyn SaveStorage>,
+    /// Set when there's a save we couldn't read or back up, so writing
+    /// over it would lose it for good
+    locked: bool,
+}
+
+impl Save {
+    /// Load the save from the given storage.
+    ///
+    /// If there's no save, or it's unreadable, start fresh. An unreadable
+    /// save gets backed up first, and if that fails it's never written
+    /// over.
+    pub fn load(mut storage: Box<dyn SaveStorage>) -> Self {
+        let mut locked = false;
+        let data = match Save::read(storage.as_ref()) {
+            Ok(Some(data)) => data,
+            Ok(None) => SaveData::default(),
+            Err(e) => {
+                warn!("couldn't load save, starting fresh: {:?}", e);
+                if let Err(e) = storage.back_up() {
+                    warn!("so progress won't be saved this run: {:?}", e);
+                    locked = true;
+                }
+                SaveData::default()
+            }
+        };
+        Self {
+            data,
+            storage,
+            locked,
+        }
+    }
+
+    fn read(storage: &dyn SaveStorage) -> eyre::Result<Option<SaveData>> {
+        let Some(src) = storage.load()? else {
+            return Ok(None);
+        };
+        let header: SaveHeader =
+            json5::from_str(&src).wrap_err("save has no version")?;
+        if header.version > SAVE_VERSION {
+            eyre::bail!(
+                "save is version {}, but we only know how to read up to {}",
+                header.version,
+                SAVE_VERSION
+            );
+        }
+        // Version 1 is missing packs, dailies, par and the time spent on
+        // boards, which all start out empty anyway
+        let mut data: SaveData =
+            json5::from_str(&src).wrap_err("malformed save")?;
+        data.version = SAVE_VERSION;
+        Ok(Some(data))
+    }
+
+    /// Load the save from the default storage for this platform.
+    pub fn open() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let storage: Box<dyn SaveStorage> = match FileStorage::new("save.json5")
+        {
+            Some(it) => Box::new(it),
+            None => {
+                warn!("no data directory, progress won't be saved");
+                Box::<MemoryStorage>::default()
+            }
+        };
+        #[cfg(target_arch = "wasm32")]
+        let storage: Box<dyn SaveStorage> = Box::<MemoryStorage>::default();
+
+        Save::load(storage)
+    }
+
+    /// Write the save out to storage.
+    pub fn commit(&mut self) {
+        if self.locked {
+            return;
+        }
+        let res = json5::to_string(&self.data)
+            .wrap_err("couldn't serialize save")
+            .and_then(|s| self.storage.store(&s));
+        if let Err(e) = res {
+            warn!("couldn't write save: {:?}", e);
+        }
+    }
+
+    /// `pack` is the id of a level pack, or `None` for the built-in levels.
+    pub fn progress(
+        &self,
+        pack: Option<&str>,
+        row: usize,
+        col: usize,
+    ) -> Option<&LevelProgress> {
+        let levels = match pack {
+            None => &self.data.levels,
+            Some(id) => self.data.packs.get(id)?,
+        };
+        levels.get(row)?.get(col)
+    }
+
+    pub fn progress_mut(
+        &mut self,
+        pack: Option<&str>,
+        row: usize,
+        col: usize,
+    ) -> &mut LevelProgress {
+        let levels = match pack {
+            None => &mut self.data.levels,
+            Some(id) => self.data.packs.entry(id.to_owned()).or_default(),
+        };
+        if levels.len() <= row {
+            levels.resize_with(row + 1, Vec::new);
+        }
+        let row = &mut levels[row];
+        if row.len() <= col {
+            row.resize_with(col + 1, LevelProgress::default);
+        }
+        &mut row[col]
+    }
+
+    pub fn is_solved(
+        &self,
+        pack: Option<&str>,
+        row: usize,
+        col: usize,
+    ) -> bool {
+        self.progress(pack, row, col).is_some_and(|p| p.solved)
+    }
+
+    /// `day` is counted since 1970-01-01, like for [`Resources::daily`].
+    ///
+    /// [`Resources::daily`]: crate::resources::Resources::daily
+    pub fn daily(&self, day: i64) -> Option<&LevelProgress> {
+        self.data.dailies.get(&iso_date(day))
+    }
+
+    pub fn daily_mut(&mut self, day: i64) -> &mut LevelProgress {
+        self.data.dailies.entry(iso_date(day)).or_default()
+    }
+
+    /// How many days in a row the daily has been solved, up to today. Not
+    /// having done today's yet doesn't break it.
+    pub fn daily_streak(&self, today: i64) -> u32 {
+        let solved = |day| self.daily(day).is_some_and(|p| p.solved);
+        let last = if solved(today) { today } else { today - 1 };
+        (0..).take_while(|back| solved(last - back)).count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Stored {
+        save: Option<String>,
+        backup: Option<String>,
+        backups_fail: bool,
+    }
+
+    /// Storage the test can still look into after handing it to a save.
+    #[derive(Clone, Default)]
+    struct TestStorage(Arc<Mutex<Stored>>);
+
+    impl TestStorage {
+        fn with_save(save: &str) -> Self {
+            let this = Self::default();
+            this.0.lock().unwrap().save = Some(save.to_owned());
+            this
+        }
+    }
+
+    impl SaveStorage for TestStorage {
+        fn load(&self) -> eyre::Result<Option<String>> {
+            Ok(self.0.lock().unwrap().save.clone())
+        }
+
+        fn store(&mut self, data: &str) -> eyre::Result<()> {
+            self.0.lock().unwrap().save = Some(data.to_owned());
+            Ok(())
+        }
+
+        fn back_up(&mut self) -> eyre::Result<()> {
+            let mut stored = self.0.lock().unwrap();
+            if stored.backups_fail {
+                eyre::bail!("disk full");
+            }
+            stored.backup = stored.save.clone();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streaks_stop_at_gaps() {
+        let mut save = Save::load(Box::<MemoryStorage>::default());
+        for day in [100, 101, 103, 104, 105] {
+            save.daily_mut(day).solved = true;
+        }
+        // Started but not finished doesn't count
+        save.daily_mut(102).board = Some(Board::default());
+
+        assert_eq!(save.daily_streak(105), 3);
+        // Today's still to do
+        assert_eq!(save.daily_streak(106), 3);
+        assert_eq!(save.daily_streak(107), 0);
+        assert_eq!(save.daily_streak(102), 2);
+    }
+
+    #[test]
+    fn upgrades_version_1() {
+        let storage = TestStorage::with_save(
+            "{version: 1, levels: [[{solved: true, best_time: 90}]]}",
+        );
+        let save = Save::load(Box::new(storage));
+        assert_eq!(save.data.version, SAVE_VERSION);
+        let progress = save.progress(None, 0, 0).unwrap();
+        assert!(progress.solved);
+        assert_eq!(progress.best_time, Some(90));
+        assert!(!progress.under_par);
+    }
+
+    #[test]
+    fn backs_up_newer_saves() {
+        let newer = "{version: 99, levels: []}";
+        let storage = TestStorage::with_save(newer);
+        let mut save = Save::load(Box::new(storage.clone()));
+        save.progress_mut(None, 0, 0).solved = true;
+        save.commit();
+
+        let stored = storage.0.lock().unwrap();
+        assert_eq!(stored.backup.as_deref(), Some(newer));
+        assert_ne!(stored.save.as_deref(), Some(newer));
+    }
+
+    #[test]
+    fn keeps_saves_it_cant_back_up() {
+        let garbage = "{version: 1, levels: 'oops'}";
+        let storage = TestStorage::with_save(garbage);
+        storage.0.lock().unwrap().backups_fail = true;
+        let mut save = Save::load(Box::new(storage.clone()));
+        save.progress_mut(None, 0, 0).solved = true;
+        save.commit();
+
+        assert_eq!(storage.0.lock().unwrap().save.as_deref(), Some(garbage));
+    }
+}
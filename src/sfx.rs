@@ -0,0 +1,38 @@
+//! Sound effects, by way of events.
+//!
+//! Game code emits events into the [`SoundQueue`] on `Resources` when
+//! something noisy happens, and the main loop drains the queue and plays
+//! whatever's in it. Nothing outside of the main loop touches the audio
+//! device.
+
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEvent {
+    EdgeOn,
+    EdgeOff,
+    CheckFailed,
+    CheckPassed,
+    ButtonHover,
+    ButtonClick,
+    TextBlip,
+}
+
+/// Sounds waiting for the end of the frame.
+///
+/// Emitting only needs a shared borrow, so states can make noise while
+/// they're also looking at a level.
+#[derive(Debug, Default)]
+pub struct SoundQueue(RefCell<Vec<SoundEvent>>);
+
+impl SoundQueue {
+    /// Queue up a sound to play at the end of the frame.
+    pub fn emit(&self, event: SoundEvent) {
+        self.0.borrow_mut().push(event);
+    }
+
+    /// Take every sound queued since the last time this was called.
+    pub fn drain(&self) -> Vec<SoundEvent> {
+        self.0.take()
+    }
+}
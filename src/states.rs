@@ -1,25 +1,65 @@
+mod cutscene;
+mod daily;
 mod gameplay;
 mod levelselect;
+mod options;
+mod packpicker;
+mod replay;
+pub use cutscene::StateCutscene;
+pub use daily::StateDaily;
 pub use gameplay::StateGameplay;
+pub use levelselect::StateLevelSelect;
+pub use options::StateOptions;
+pub use packpicker::StatePackPicker;
+pub use replay::StateReplay;
 
 use enum_dispatch::enum_dispatch;
 use gerrymander::Transition;
 
+use crate::{input::Input, music::Track, resources::Resources};
+
 #[enum_dispatch]
 pub trait GameStateDispatch: Sized {
     /// Good ol' 60hz update.
-    fn update(&mut self) -> Transition<GameState>;
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState>;
+
+    fn draw(&self, res: &Resources);
 
-    fn draw(&self);
+    /// What should be playing while this state is on top, or `None` for
+    /// silence.
+    fn music(&self) -> Option<Track> {
+        None
+    }
+
+    /// Should the music be quieter, say to make room for dialogue?
+    fn ducks_music(&self) -> bool {
+        false
+    }
 }
 
 #[enum_dispatch(GameStateDispatch)]
+#[allow(clippy::large_enum_variant)]
 pub enum GameState {
+    LevelSelect(StateLevelSelect),
     Gameplay(StateGameplay),
+    Cutscene(StateCutscene),
+    Daily(StateDaily),
+    Options(StateOptions),
+    PackPicker(StatePackPicker),
+    Replay(StateReplay),
 }
 
 impl GameState {
-    pub fn start() -> Self {
-        GameState::Gameplay(StateGameplay::new(0, 0))
+    pub fn start(res: &Resources) -> Self {
+        // Nobody needs to pick a pack if there's only the one
+        if !res.any_packs() {
+            GameState::LevelSelect(StateLevelSelect::new(res, None))
+        } else {
+            GameState::PackPicker(StatePackPicker::new(res))
+        }
     }
 }
@@ -0,0 +1,200 @@
+use gerrymander::Transition;
+use macroquad::prelude::*;
+
+use crate::{
+    cutscene::Step,
+    input::{Action, Input},
+    music::Track,
+    resources::Resources,
+    text::{draw_pixel_text, TextAlign, TextBox},
+    util::{hexcolor, patch9},
+    HEIGHT,
+};
+
+use super::{GameState, GameStateDispatch};
+
+const BILLBOARD_Y: f32 = HEIGHT - 48.0;
+const TEXT_X: f32 = 8.0 + 5.0;
+/// Where the text goes when there's a portrait to the left of it
+const TEXT_X_PORTRAIT: f32 = TEXT_X + 16.0 + 4.0;
+const TEXT_RIGHT: f32 = 8.0 + 19.0 * 16.0 - 5.0;
+const TEXT_LINES: usize = 5;
+
+pub struct StateCutscene {
+    row: usize,
+    /// Index of the step we're on
+    step: usize,
+    /// Frames spent on the current step
+    step_frames: u32,
+
+    ufo_pos: Vec2,
+    /// Where the UFO was when the current step started
+    ufo_from: Vec2,
+
+    /// The dialogue for the current step, if it's a line of dialogue
+    text_box: Option<TextBox>,
+
+    frames: u64,
+}
+
+impl StateCutscene {
+    pub fn new(res: &Resources, row: usize) -> Self {
+        let ufo_pos = res
+            .cutscenes
+            .get(row)
+            .map_or(Vec2::ZERO, |scene| Vec2::from(scene.ufo));
+
+        Self {
+            row,
+            step: 0,
+            step_frames: 0,
+            ufo_pos,
+            ufo_from: ufo_pos,
+            text_box: None,
+            frames: 0,
+        }
+    }
+
+    fn next_step(&mut self) {
+        self.step += 1;
+        self.step_frames = 0;
+        self.ufo_from = self.ufo_pos;
+        self.text_box = None;
+    }
+}
+
+impl GameStateDispatch for StateCutscene {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        self.frames += 1;
+
+        if input.pressed(Action::Back) {
+            return Transition::Pop;
+        }
+
+        let Some(step) = res
+            .cutscenes
+            .get(self.row)
+            .and_then(|scene| scene.steps.get(self.step))
+        else {
+            return Transition::Pop;
+        };
+
+        // There's nothing to check in here, so let Space skip along too
+        let advance = input.mouse_pressed
+            || input.pressed(Action::Check)
+            || input.pressed(Action::Confirm);
+
+        self.step_frames += 1;
+        let done = match step {
+            Step::Say { speaker, line } => {
+                let text_box = self.text_box.get_or_insert_with(|| {
+                    let x = if speaker.is_some() {
+                        TEXT_X_PORTRAIT
+                    } else {
+                        TEXT_X
+                    };
+                    TextBox::new(
+                        res.locales.dialogue(line),
+                        TEXT_RIGHT - x,
+                        TEXT_LINES,
+                        res.textures.font,
+                    )
+                    .with_reveal(1.0)
+                });
+                text_box.update(res);
+                advance && text_box.advance()
+            }
+            Step::Pause(frames) => self.step_frames >= *frames,
+            Step::MoveUfo { x, y, frames } => {
+                let t = (self.step_frames as f32 / *frames as f32).min(1.0);
+                // ease in and out
+                let t = t * t * (3.0 - 2.0 * t);
+                self.ufo_pos = self.ufo_from.lerp(vec2(*x, *y), t);
+                self.step_frames >= *frames
+            }
+        };
+        if done {
+            self.next_step();
+        }
+
+        Transition::None
+    }
+
+    fn draw(&self, res: &Resources) {
+        let Some(scene) = res.cutscenes.get(self.row) else {
+            return;
+        };
+
+        clear_background(hexcolor(0x42756bff));
+
+        let bob = if self.frames % 64 < 32 || res.config.data.reduced_motion {
+            0.0
+        } else {
+            1.0
+        };
+        draw_texture_ex(
+            res.textures.ufo,
+            self.ufo_pos.x.round(),
+            self.ufo_pos.y.round() + bob,
+            WHITE,
+            DrawTextureParams {
+                source: Some(Rect::new(0.0, 0.0, 16.0, 16.0)),
+                ..Default::default()
+            },
+        );
+
+        let (Some(Step::Say { speaker, .. }), Some(text_box)) =
+            (scene.steps.get(self.step), &self.text_box)
+        else {
+            return;
+        };
+
+        let by = BILLBOARD_Y;
+        patch9(16.0, TEXT_X - 5.0, by, 19, 4, res.textures.billboard);
+
+        let speaker =
+            speaker.as_ref().and_then(|s| res.cutscenes.speakers.get(s));
+        let text_x = if let Some(speaker) = speaker {
+            draw_texture_ex(
+                res.textures.portraits,
+                TEXT_X,
+                by + 5.0,
+                WHITE,
+                DrawTextureParams {
+                    source: Some(Rect::new(
+                        speaker.portrait as f32 * 16.0,
+                        0.0,
+                        16.0,
+                        16.0,
+                    )),
+                    ..Default::default()
+                },
+            );
+            draw_pixel_text(
+                res.locales.speaker_name(&speaker.name),
+                TEXT_X_PORTRAIT,
+                by + 5.0,
+                TextAlign::Left,
+                hexcolor(0x2eb368ff),
+                res,
+            );
+            TEXT_X_PORTRAIT
+        } else {
+            TEXT_X
+        };
+
+        text_box.draw(text_x, by + 5.0 + 8.0, BLACK, res);
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Row(self.row))
+    }
+
+    fn ducks_music(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,230 @@
+use gerrymander::Transition;
+use macroquad::{miniquad::date, prelude::*};
+
+use crate::{
+    button::{tab_focus, Button},
+    generate::iso_date,
+    input::{Action, Input},
+    music::Track,
+    resources::Resources,
+    sfx::SoundEvent,
+    text::{draw_pixel_text, measure_line, TextAlign},
+    util::{hexcolor, patch9},
+    HEIGHT, WIDTH,
+};
+
+use super::{
+    gameplay::format_time, GameState, GameStateDispatch, StateGameplay,
+};
+
+const TOP_Y: f32 = 44.0;
+const ROW_SPACING: f32 = 14.0;
+const BUTTON_X: f32 = 32.0;
+const BUTTON_WIDTH: f32 = 100.0;
+const STATUS_X: f32 = 144.0;
+const DAYS_PER_PAGE: i64 = 10;
+
+/// Today's puzzle, and every one before it, with the streak so far.
+pub struct StateDaily {
+    /// Counted in days since 1970-01-01 UTC, like [`Resources::daily`]
+    today: i64,
+    /// How many pages back from today we're looking
+    page: i64,
+    /// One for each day on the page, newest first
+    b_days: Vec<Button>,
+    b_older: Button,
+    /// Only there when not looking at today
+    b_newer: Option<Button>,
+    b_back: Button,
+}
+
+impl StateDaily {
+    pub fn new(res: &Resources) -> Self {
+        // Days turn over at midnight UTC, not local midnight, so everyone
+        // in the world is on the same puzzle at once
+        let today = (date::now() / 86_400.0).floor() as i64;
+        let b_back = nav_button(res, "back", 16.0);
+        let b_older = nav_button(res, "older", b_back.x() + b_back.w() + 8.0);
+        Self {
+            today,
+            page: 0,
+            b_days: day_buttons(res, today, 0),
+            b_older,
+            b_newer: None,
+            b_back,
+        }
+    }
+
+    fn first_day(&self) -> i64 {
+        self.today - self.page * DAYS_PER_PAGE
+    }
+
+    fn turn_page(&mut self, res: &Resources, page: i64) {
+        self.page = page;
+        self.b_days = day_buttons(res, self.today, page);
+        self.b_newer = (page > 0).then(|| {
+            let x = self.b_older.x() + self.b_older.w() + 8.0;
+            nav_button(res, "newer", x)
+        });
+    }
+}
+
+fn day_buttons(res: &Resources, today: i64, page: i64) -> Vec<Button> {
+    (0..DAYS_PER_PAGE)
+        .map(|idx| {
+            let day = today - page * DAYS_PER_PAGE - idx;
+            let label = if day == today {
+                res.locales.ui("today").to_owned()
+            } else {
+                iso_date(day)
+            };
+            Button::new(
+                BUTTON_X,
+                TOP_Y + idx as f32 * ROW_SPACING - 2.0,
+                BUTTON_WIDTH,
+                9.0,
+                Some((label, TextAlign::Center)),
+            )
+        })
+        .collect()
+}
+
+fn nav_button(res: &Resources, key: &str, x: f32) -> Button {
+    let label = res.locales.ui(key);
+    let w = measure_line(label, res.textures.font) + 4.0;
+    Button::new(
+        x,
+        HEIGHT - 28.0,
+        w,
+        9.0,
+        Some((label.to_owned(), TextAlign::Center)),
+    )
+}
+
+impl GameStateDispatch for StateDaily {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        tab_focus(
+            res,
+            input,
+            self.b_days
+                .iter_mut()
+                .chain(std::iter::once(&mut self.b_older))
+                .chain(self.b_newer.as_mut())
+                .chain(std::iter::once(&mut self.b_back)),
+        );
+
+        let picked = self.b_days.iter().position(|b| b.pressed(input));
+        let older = self.b_older.pressed(input);
+        let newer = self.b_newer.as_ref().is_some_and(|b| b.pressed(input));
+        let back = self.b_back.pressed(input) || input.pressed(Action::Back);
+        for b in self
+            .b_days
+            .iter_mut()
+            .chain(std::iter::once(&mut self.b_older))
+            .chain(self.b_newer.as_mut())
+            .chain(std::iter::once(&mut self.b_back))
+        {
+            b.post_update(res, input);
+        }
+
+        if let Some(idx) = picked {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            let day = self.first_day() - idx as i64;
+            Transition::Push(GameState::Gameplay(StateGameplay::daily(
+                res, day,
+            )))
+        } else if older || newer {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            let page = if older { self.page + 1 } else { self.page - 1 };
+            self.turn_page(res, page);
+            Transition::None
+        } else if back {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, res: &Resources) {
+        let save = &res.save;
+
+        clear_background(Color::from_rgba(139, 179, 146, 255));
+        patch9(16.0, 8.0, 8.0, 19, 14, res.textures.billboard);
+
+        draw_pixel_text(
+            res.locales.ui("daily_puzzle"),
+            WIDTH / 2.0,
+            20.0,
+            TextAlign::Center,
+            BLACK,
+            res,
+        );
+        draw_pixel_text(
+            &format!(
+                "{} {}",
+                res.locales.ui("streak"),
+                save.daily_streak(self.today)
+            ),
+            WIDTH / 2.0,
+            29.0,
+            TextAlign::Center,
+            BLACK,
+            res,
+        );
+
+        for (idx, b) in self
+            .b_days
+            .iter()
+            .chain(std::iter::once(&self.b_older))
+            .chain(&self.b_newer)
+            .chain(std::iter::once(&self.b_back))
+            .enumerate()
+        {
+            b.draw(
+                hexcolor(0x75d9d0ff),
+                hexcolor(0x42756bff),
+                hexcolor(0x99e8e0ff),
+                hexcolor(0x2eb368ff),
+                1.0,
+                res,
+            );
+
+            let Some(progress) = (idx < self.b_days.len())
+                .then(|| save.daily(self.first_day() - idx as i64))
+                .flatten()
+            else {
+                continue;
+            };
+            let (text, color) = match (progress.best_time, progress.best_moves)
+            {
+                (Some(time), Some(moves)) if progress.solved => (
+                    format!(
+                        "{}  {} {}",
+                        format_time(time),
+                        moves,
+                        res.locales.ui("moves")
+                    ),
+                    hexcolor(0x2eb368ff),
+                ),
+                _ => (res.locales.ui("started").to_owned(), BLACK),
+            };
+            draw_pixel_text(
+                &text,
+                STATUS_X,
+                TOP_Y + idx as f32 * ROW_SPACING,
+                TextAlign::Left,
+                color,
+                res,
+            );
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Menu)
+    }
+}
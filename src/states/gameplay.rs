@@ -4,13 +4,20 @@ mod update;
 use std::f32::consts::TAU;
 
 use gerrymander::Transition;
-use hex2d::{Angle, Coordinate, Direction, IntegerSpacing};
-use macroquad::prelude::{vec2, Mat2, Mat3, Vec2};
+use hex2d::{Coordinate, Direction};
+use macroquad::prelude::{vec2, Mat3, Rect, Vec2};
 
 use crate::{
     button::Button,
-    geom::{EdgePos, HexEdge},
-    puzzle::Board,
+    generate,
+    geom::EdgePos,
+    input::Input,
+    music::Track,
+    puzzle::{Board, Level, PuzzleShape},
+    replay::Replay,
+    resources::Resources,
+    save::{LevelProgress, Save},
+    text::{TextAlign, TextBox},
     HEIGHT, WIDTH,
 };
 
@@ -23,9 +30,26 @@ const HEX_SPAN_X: i32 = 32;
 const HEX_SPAN_Y: i32 = 24;
 
 const BOARD_CENTER_X: f32 = WIDTH / 2.0;
-const BOARD_CENTER_Y: f32 = HEIGHT / 2.0 - HEX_WIDTH as f32;
+const BOARD_CENTER_Y: f32 = HEIGHT / 2.0 - HEX_WIDTH;
 
 const PATH_MIN_DIST: f32 = 0.5;
+/// Fingers are fatter than the mouse, so paths can be grabbed from nearer
+/// the middle of the hex.
+const TOUCH_PATH_MIN_DIST: f32 = 0.8;
+/// How far a finger moves before it's a drag and not a tap, in pixels.
+const TOUCH_DRAG_START: f32 = 3.0;
+/// How many boards back undo remembers
+const UNDO_LIMIT: usize = 256;
+/// For turning `frames` into a time; it's a 60hz update
+const UPDATES_PER_SECOND: u64 = 60;
+
+const BILLBOARD: Rect = Rect {
+    x: 8.0,
+    y: HEIGHT - 48.0,
+    w: 19.0 * 16.0,
+    h: 48.0,
+};
+const DESCRIPTION_LINES: usize = 7;
 
 const MAT_COORD2PX: Mat3 = Mat3::from_cols_array(&[
     HEX_SPAN_X as f32,
@@ -43,52 +67,280 @@ const MAT_COORD2PX: Mat3 = Mat3::from_cols_array(&[
 
 pub struct StateGameplay {
     board: Board,
+    /// Index into `Resources::packs`, or `None` for the built-in levels
+    pack: Option<usize>,
+    /// What the pack's progress is saved under
+    pack_id: Option<String>,
     level_idxs: (usize, usize),
+    /// The day of the daily puzzle, if that's what this is instead of a
+    /// level from a pack
+    daily: Option<i64>,
+    /// What the puzzle looked like when the board was made, in case it gets
+    /// hot reloaded out from under us
+    shape: PuzzleShape,
 
     /// None for not painting, Some(x) for turning it on or off
     painting_path: Option<bool>,
+    /// Where the keyboard cursor is, if the keyboard's been used
+    cursor: Option<Coordinate>,
+    /// Where the mouse was as of the last update
+    mouse: Vec2,
+    /// Older boards, most recent last
+    undo: Vec<Board>,
+    /// Boards that were undone, most recent last
+    redo: Vec<Board>,
+    touch: TouchGesture,
+    /// Has the player touched the screen? If so, show the finger buttons.
+    touch_mode: bool,
 
     b_check: Button,
     b_back: Button,
     b_help: Button,
+    /// Bigger buttons for touchscreens, indexed by `FingerButton`
+    b_fingers: [Button; 4],
+
+    description: TextBox,
 
     frames: u64,
+    /// How many times a path has been toggled this attempt
+    moves: u32,
     check_state: CheckState,
+    /// Did the solve just now beat the player's best time or moves?
+    new_best: bool,
+    /// Has the board changed since it was last saved?
+    unsaved: bool,
+    /// The language might have changed while we were in the options
+    relabel: bool,
+    /// Is this attempt going into `res.recorder`? Not if it's itself a
+    /// replay, which mustn't touch the save either.
+    recording: bool,
 }
 
 impl StateGameplay {
-    pub fn new(level_row: usize, level_col: usize) -> Self {
+    pub fn new(
+        res: &Resources,
+        pack: Option<usize>,
+        level_row: usize,
+        level_col: usize,
+    ) -> Self {
+        let level = res.pack_levels(pack).get(level_row, level_col).unwrap();
+        Self::start(res, level, pack, (level_row, level_col), None)
+    }
+
+    /// Play the daily puzzle for the day; see [`Resources::daily`].
+    pub fn daily(res: &mut Resources, day: i64) -> Self {
+        res.daily(day);
+        Self::start(
+            res,
+            &res.dailies[&day],
+            None,
+            // The music and background go by the day of the week
+            (generate::weekday(day), 0),
+            Some(day),
+        )
+    }
+
+    fn start(
+        res: &Resources,
+        level: &Level,
+        pack: Option<usize>,
+        level_idxs: (usize, usize),
+        daily: Option<i64>,
+    ) -> Self {
         let bx = 3.0;
         let by = 3.0;
         let b_check = Button::new(bx, by, 9.0, 9.0, None);
         let b_back = Button::new(bx, by + 10.0, 9.0, 9.0, None);
         let b_help = Button::new(bx, by + 20.0, 9.0, 9.0, None);
 
-        Self {
-            board: Board::new(),
-            level_idxs: (level_row, level_col),
+        let b_fingers = finger_buttons(res);
+        let description = description_box(res, pack, level, daily);
+
+        let mut this = Self {
+            board: Board::default(),
+            pack,
+            pack_id: res.pack_id(pack).map(str::to_owned),
+            level_idxs,
+            daily,
+            shape: level.puzzle.shape(),
             painting_path: None,
+            cursor: None,
+            mouse: Vec2::ZERO,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            touch: TouchGesture::Idle,
+            touch_mode: false,
 
             b_check,
             b_back,
             b_help,
+            b_fingers,
+
+            description,
 
             frames: 0,
+            moves: 0,
             check_state: CheckState::Waiting,
+            new_best: false,
+            unsaved: false,
+            relabel: false,
+            recording: true,
+        };
+
+        // Pick up where the player left off, clock and all
+        if let Some(progress) = this.progress(&res.save) {
+            if let Some(board) = &progress.board {
+                this.board = board.clone();
+                this.frames = progress.frames;
+                this.moves = progress.moves;
+            }
+        }
+        res.recorder.start(Replay::new(
+            this.pack_id.clone(),
+            level.id.clone(),
+            daily,
+            this.board.clone(),
+        ));
+        this
+    }
+
+    /// Set up the level a replay was recorded on, as it was when the
+    /// recording started.
+    pub fn replaying(
+        res: &mut Resources,
+        replay: &Replay,
+    ) -> eyre::Result<Self> {
+        if let Some(day) = replay.daily {
+            let mut this = Self::daily(res, day);
+            this.watch(res, replay);
+            return Ok(this);
+        }
+
+        let pack = match &replay.pack {
+            None => None,
+            Some(id) => Some(
+                res.packs
+                    .iter()
+                    .position(|pack| &pack.id == id)
+                    .ok_or_else(|| eyre::eyre!("no level pack {:?}", id))?,
+            ),
+        };
+        let (row, col) = res
+            .pack_levels(pack)
+            .rows
+            .iter()
+            .enumerate()
+            .find_map(|(row, levels)| {
+                let col = levels.iter().position(|l| l.id == replay.level)?;
+                Some((row, col))
+            })
+            .ok_or_else(|| eyre::eyre!("no level {:?}", replay.level))?;
+
+        let mut this = Self::new(res, pack, row, col);
+        this.watch(res, replay);
+        Ok(this)
+    }
+
+    /// Swap the player's own attempt out for the start of the replay.
+    fn watch(&mut self, res: &Resources, replay: &Replay) {
+        self.board = replay.start.clone();
+        self.frames = 0;
+        self.moves = 0;
+        self.recording = false;
+        res.recorder.stop();
+    }
+
+    fn level<'r>(&self, res: &'r Resources) -> &'r Level {
+        match self.daily {
+            Some(day) => &res.dailies[&day],
+            None => res
+                .pack_levels(self.pack)
+                .get(self.level_idxs.0, self.level_idxs.1)
+                .unwrap(),
+        }
+    }
+
+    fn progress<'s>(&self, save: &'s Save) -> Option<&'s LevelProgress> {
+        match self.daily {
+            Some(day) => save.daily(day),
+            None => {
+                let (row, col) = self.level_idxs;
+                save.progress(self.pack_id.as_deref(), row, col)
+            }
         }
     }
+
+    fn progress_mut<'s>(&self, save: &'s mut Save) -> &'s mut LevelProgress {
+        match self.daily {
+            Some(day) => save.daily_mut(day),
+            None => {
+                let (row, col) = self.level_idxs;
+                save.progress_mut(self.pack_id.as_deref(), row, col)
+            }
+        }
+    }
+}
+
+fn finger_buttons(res: &Resources) -> [Button; 4] {
+    FingerButton::ALL.map(|b| {
+        Button::new(
+            WIDTH - 3.0 - FINGER_BUTTON_WIDTH,
+            3.0 + b as usize as f32 * (FINGER_BUTTON_HEIGHT + 3.0),
+            FINGER_BUTTON_WIDTH,
+            FINGER_BUTTON_HEIGHT,
+            Some((res.locales.ui(b.ui_key()).to_owned(), TextAlign::Center)),
+        )
+    })
+}
+
+/// The level's description, typing itself out in whatever language is on.
+fn description_box(
+    res: &Resources,
+    pack: Option<usize>,
+    level: &Level,
+    daily: Option<i64>,
+) -> TextBox {
+    let description = match daily {
+        Some(day) => format!(
+            "{} {}",
+            res.locales.ui("daily_puzzle"),
+            generate::iso_date(day)
+        ),
+        None => res.level_description(pack, &level.id).to_owned(),
+    };
+    TextBox::new(
+        &description,
+        BILLBOARD.w - 6.0,
+        DESCRIPTION_LINES,
+        res.textures.font,
+    )
+    .with_reveal(2.0)
 }
 
 impl GameStateDispatch for StateGameplay {
-    fn update(&mut self) -> Transition<GameState> {
-        self.update_()
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        self.update_(res, input)
+    }
+
+    fn draw(&self, res: &Resources) {
+        self.draw_(res);
     }
 
-    fn draw(&self) {
-        self.draw_();
+    fn music(&self) -> Option<Track> {
+        Some(Track::Row(self.level_idxs.0))
     }
 }
 
+/// Like `1:05`
+pub fn format_time(frames: u64) -> String {
+    let seconds = frames / UPDATES_PER_SECOND;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
 // https://github.com/gamma-delta/haxagon/blob/0131b392adb50b03d66eb18a0105694dd1deb713/src/modes/playing/mod.rs#L349
 fn px_to_coord(px: Vec2) -> Coordinate {
     let tf = MAT_COORD2PX.inverse();
@@ -114,10 +366,6 @@ fn coord_to_px(coord: Coordinate) -> Vec2 {
 }
 
 // https://github.com/gamma-delta/hexlife/blob/99b5e182d8916b61b13e2f99da17841c9b4f6e69/viewer/src/main.rs#L66
-fn px_to_edge(px: Vec2) -> EdgePos {
-    far_px_to_edge(px, 0.0).unwrap()
-}
-
 fn far_px_to_edge(px: Vec2, tolerance: f32) -> Option<EdgePos> {
     let coord = px_to_coord(px);
 
@@ -135,9 +383,278 @@ fn far_px_to_edge(px: Vec2, tolerance: f32) -> Option<EdgePos> {
     Some(EdgePos::new(coord, dir))
 }
 
+const FINGER_BUTTON_WIDTH: f32 = 40.0;
+const FINGER_BUTTON_HEIGHT: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FingerButton {
+    Check,
+    Undo,
+    Redo,
+    Back,
+}
+
+impl FingerButton {
+    const ALL: [FingerButton; 4] = [
+        FingerButton::Check,
+        FingerButton::Undo,
+        FingerButton::Redo,
+        FingerButton::Back,
+    ];
+
+    fn ui_key(self) -> &'static str {
+        match self {
+            FingerButton::Check => "check",
+            FingerButton::Undo => "undo",
+            FingerButton::Redo => "redo",
+            FingerButton::Back => "back",
+        }
+    }
+}
+
+/// What the fingers on the board are doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TouchGesture {
+    Idle,
+    /// One finger down that hasn't moved far enough to be a drag yet
+    Holding {
+        id: u64,
+        start: Vec2,
+    },
+    Drawing {
+        id: u64,
+    },
+    /// More than one finger went down; wait for them all to lift
+    MultiFinger,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CheckState {
     Waiting,
     No(u32),
     Yes(u32),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geom::HexEdge,
+        input::{Action, InputSource, ScriptedInput, Walk},
+        puzzle::Puzzle,
+        save::MemoryStorage,
+        sfx::SoundEvent,
+    };
+
+    /// Feed the whole script in, one update at a time.
+    fn play(
+        state: &mut StateGameplay,
+        res: &mut Resources,
+        mut script: ScriptedInput,
+    ) {
+        while !script.is_done() {
+            let input = script.next_input();
+            state.update(res, &input);
+        }
+    }
+
+    /// Every path on the board, as the two cells it joins.
+    fn paths(puzzle: &Puzzle, board: &Board) -> Vec<[Coordinate; 2]> {
+        Coordinate::new(0, 0)
+            .range_iter(puzzle.radius() as i32)
+            .flat_map(|coord| {
+                [HexEdge::XY, HexEdge::ZY, HexEdge::ZX]
+                    .into_iter()
+                    .filter(move |edge| {
+                        board.get_raw_paths(coord).contains(*edge)
+                    })
+                    .map(move |edge| EdgePos::new_raw(coord, edge).spans())
+            })
+            .collect()
+    }
+
+    fn first_puzzle(res: &Resources) -> &Puzzle {
+        &res.levels.get(0, 0).unwrap().puzzle
+    }
+
+    /// The solver's answer to the first built-in level.
+    fn first_solution(res: &Resources) -> Vec<[Coordinate; 2]> {
+        let puzzle = first_puzzle(res);
+        paths(puzzle, &puzzle.solve().unwrap().board)
+    }
+
+    /// Run one update and hear what came of it, apart from the description
+    /// typing itself out.
+    fn sounds(
+        state: &mut StateGameplay,
+        res: &mut Resources,
+        input: Input,
+    ) -> Vec<SoundEvent> {
+        state.update(res, &input);
+        let mut events = res.sfx.drain();
+        events.retain(|ev| *ev != SoundEvent::TextBlip);
+        events
+    }
+
+    fn walk(dir: Direction) -> Input {
+        Input {
+            walk: Some(Walk { dir, draw: true }),
+            ..Default::default()
+        }
+    }
+
+    fn press(action: Action) -> Input {
+        Input {
+            actions: action.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_up_the_saved_board() {
+        let mut res = Resources::stub();
+        let edge = EdgePos::new(Coordinate::new(0, 0), Direction::XY);
+        let mut board = Board::default();
+        board.set_path(first_puzzle(&res), edge, true);
+        let progress = res.save.progress_mut(None, 0, 0);
+        progress.board = Some(board);
+        progress.frames = 600;
+        progress.moves = 1;
+
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+        assert_eq!(state.board.get_path(edge), Some(true));
+        let trans = state.update(&mut res, &Input::default());
+        assert!(matches!(trans, Transition::None));
+        // The clock carries on from where it was, not from nothing
+        assert_eq!((state.frames, state.moves), (601, 1));
+
+        state.update(&mut res, &press(Action::Back));
+        let progress = res.save.progress(None, 0, 0).unwrap();
+        assert_eq!((progress.frames, progress.moves), (601, 1));
+    }
+
+    /// One stroke per path, from the middle of one cell to the other, and
+    /// then a click on the check button.
+    fn drag_out(paths: &[[Coordinate; 2]]) -> ScriptedInput {
+        paths
+            .iter()
+            .fold(ScriptedInput::new(), |script, [a, b]| {
+                script.drag(coord_to_px(*a), coord_to_px(*b), 8).wait(2)
+            })
+            .click(vec2(7.0, 7.0))
+    }
+
+    #[test]
+    fn drags_out_a_solution() {
+        let mut res = Resources::stub();
+        let paths = first_solution(&res);
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+        play(&mut state, &mut res, drag_out(&paths));
+
+        assert!(state.board.is_solved(first_puzzle(&res)).is_ok());
+        assert_eq!(state.moves, paths.len() as u32);
+        assert!(matches!(state.check_state, CheckState::Yes(_)));
+        assert!(res.save.is_solved(None, 0, 0));
+    }
+
+    #[test]
+    fn replays_play_back_the_same() {
+        let mut res = Resources::stub();
+        let script = drag_out(&first_solution(&res));
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+        play(&mut state, &mut res, script);
+        let replay = res.recorder.stop().unwrap();
+        // By way of the file, too
+        let replay: Replay =
+            json5::from_str(&json5::to_string(&replay).unwrap()).unwrap();
+
+        res.save = Save::load(Box::<MemoryStorage>::default());
+        let mut watched = StateGameplay::replaying(&mut res, &replay).unwrap();
+        play(&mut watched, &mut res, replay.script());
+
+        let puzzle = first_puzzle(&res);
+        assert_eq!(paths(puzzle, &watched.board), paths(puzzle, &state.board));
+        assert_eq!(watched.moves, state.moves);
+        assert!(matches!(watched.check_state, CheckState::Yes(_)));
+        // Watching doesn't count as playing
+        assert!(res.recorder.stop().is_none());
+        assert!(res.save.progress(None, 0, 0).is_none());
+    }
+
+    #[test]
+    fn walks_out_a_solution() {
+        let mut res = Resources::stub();
+        let paths = first_solution(&res);
+
+        // The first step just shows where the cursor starts
+        let puzzle = first_puzzle(&res);
+        let mut cursor = Coordinate::new(0, 0)
+            .range_iter(puzzle.radius() as i32)
+            .find(|c| puzzle.is_valid(*c))
+            .unwrap();
+        let mut script = ScriptedInput::new().walk(Direction::XY, false);
+        for [a, b] in &paths {
+            while cursor != *a {
+                let dir = cursor.direction_to_cw(*a).unwrap();
+                script = script.walk(dir, false);
+                cursor = cursor + dir;
+            }
+            script = script.walk(a.direction_to_cw(*b).unwrap(), true);
+            cursor = *b;
+        }
+        let script = script.press(Action::Check).wait(1);
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+        play(&mut state, &mut res, script);
+
+        assert!(state.board.is_solved(first_puzzle(&res)).is_ok());
+        assert_eq!(state.moves, paths.len() as u32);
+        assert!(matches!(state.check_state, CheckState::Yes(_)));
+    }
+
+    #[test]
+    fn edges_make_noise() {
+        let mut res = Resources::stub();
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+
+        // The first step only shows the cursor
+        assert_eq!(sounds(&mut state, &mut res, walk(Direction::XY)), []);
+        assert_eq!(
+            sounds(&mut state, &mut res, walk(Direction::XY)),
+            [SoundEvent::EdgeOn]
+        );
+        assert_eq!(
+            sounds(&mut state, &mut res, press(Action::Undo)),
+            [SoundEvent::EdgeOff]
+        );
+        assert_eq!(
+            sounds(&mut state, &mut res, press(Action::Redo)),
+            [SoundEvent::EdgeOn]
+        );
+        assert_eq!(
+            sounds(&mut state, &mut res, walk(Direction::YX)),
+            [SoundEvent::EdgeOff]
+        );
+        // Nothing to redo after drawing
+        assert_eq!(sounds(&mut state, &mut res, press(Action::Redo)), []);
+    }
+
+    #[test]
+    fn checking_makes_noise() {
+        let mut res = Resources::stub();
+        let mut state = StateGameplay::new(&res, None, 0, 0);
+
+        assert_eq!(
+            sounds(&mut state, &mut res, press(Action::Check)),
+            [SoundEvent::CheckFailed]
+        );
+        // Still showing the last result, so no new sound
+        assert_eq!(sounds(&mut state, &mut res, press(Action::Check)), []);
+
+        state.board = first_puzzle(&res).solve().unwrap().board;
+        state.check_state = CheckState::Waiting;
+        assert_eq!(
+            sounds(&mut state, &mut res, press(Action::Check)),
+            [SoundEvent::CheckPassed]
+        );
+    }
+}
@@ -8,29 +8,26 @@ use std::{
 use crate::{
     geom::{EdgePos, HexEdge},
     puzzle::{Level, Puzzle},
-    resources::{Resources, ResourcesRef},
-    text::{draw_pixel_text, TextAlign},
-    util::{hexcolor, mouse_position_pixel, patch9},
+    resources::Resources,
+    text::{draw_pixel_text, line_advance, TextAlign},
+    util::{hexcolor, patch9},
     HEIGHT, WIDTH,
 };
 
 use super::{
-    coord_to_px, far_px_to_edge, px_to_edge, CheckState, StateGameplay,
-    HEX_HEIGHT, HEX_WIDTH, PATH_MIN_DIST,
+    coord_to_px, far_px_to_edge, format_time, CheckState, StateGameplay,
+    BILLBOARD, FINGER_BUTTON_HEIGHT, HEX_HEIGHT, HEX_WIDTH, PATH_MIN_DIST,
+    UPDATES_PER_SECOND,
 };
 
-use hex2d::{Angle, Coordinate, Direction};
+use hex2d::{Coordinate, Direction};
 use macroquad::prelude::*;
 
 impl StateGameplay {
-    pub(super) fn draw_(&self) {
-        let res = Resources::get();
-        let level = &res
-            .levels
-            .get(self.level_idxs.0, self.level_idxs.1)
-            .unwrap();
+    pub(super) fn draw_(&self, res: &Resources) {
+        let level = self.level(res);
 
-        self.draw_background(&res);
+        self.draw_background(res);
 
         let coords = {
             let mut range = Coordinate::new(0, 0)
@@ -48,7 +45,7 @@ impl StateGameplay {
         }
 
         for (coord, center) in coords.iter().copied() {
-            self.draw_junctions(&level.puzzle, &res, coord, center);
+            self.draw_junctions(&level.puzzle, res, coord, center);
         }
 
         // Draw center dots on top
@@ -67,6 +64,18 @@ impl StateGameplay {
             }
         }
 
+        if let Some(cursor) = self.cursor {
+            let center = coord_to_px(cursor);
+            draw_rectangle_lines(
+                center.x - 4.0,
+                center.y - 4.0,
+                8.0,
+                8.0,
+                1.0,
+                hexcolor(0xcc2d47ff),
+            );
+        }
+
         // Draw edge numbers
         for (marks, (dir, start, deltas)) in level.puzzle.marks().iter().zip([
             (Direction::XY, (-22.0, -2.0), (-6.0, 0.0)),
@@ -74,13 +83,18 @@ impl StateGameplay {
             (Direction::ZX, (8.0, -19.0), (3.0, -6.0)),
         ]) {
             // scan the flank
-            draw_flank_numbers(marks, level, dir, start, deltas, &res);
+            draw_flank_numbers(marks, level, dir, start, deltas, res);
         }
 
-        self.draw_ui(&res, &level);
+        self.draw_ui(res);
+        self.draw_stats(res, level);
     }
 
     fn draw_background(&self, res: &Resources) {
+        let tint = self
+            .pack
+            .and_then(|idx| res.packs[idx].tint)
+            .unwrap_or(WHITE);
         for cell_x in 0..WIDTH as u32 / 16 {
             for cell_y in 0..HEIGHT as u32 / 16 {
                 let px = cell_x as f32 * 16.0;
@@ -88,8 +102,8 @@ impl StateGameplay {
 
                 let sx = hash((cell_x + 1, 0x1234, self.level_idxs)) % 48;
                 let sy = hash((cell_y + 1, 0x5678, self.level_idxs)) % 48;
-                let flip_x = hash((cell_y + 2, 0x7604)) % 2 == 0;
-                let flip_y = hash((cell_x + 2, 0o7604)) % 2 == 0;
+                let flip_x = hash((cell_y + 2, 0x7604)).is_multiple_of(2);
+                let flip_y = hash((cell_x + 2, 0o7604)).is_multiple_of(2);
                 let rotation = (hash((cell_x, cell_y, self.level_idxs)) % 4)
                     as f32
                     * 0.25
@@ -98,7 +112,7 @@ impl StateGameplay {
                     res.textures.background,
                     px,
                     py,
-                    WHITE,
+                    tint,
                     DrawTextureParams {
                         source: Some(Rect::new(sx as _, sy as _, 16.0, 16.0)),
                         flip_x,
@@ -118,25 +132,26 @@ impl StateGameplay {
         coord: Coordinate,
         center: Vec2,
     ) {
-        let mouse_edge = far_px_to_edge(mouse_position_pixel(), PATH_MIN_DIST);
+        let mouse_edge = far_px_to_edge(self.mouse, PATH_MIN_DIST);
+        let pulse = if res.config.data.reduced_motion {
+            0.5
+        } else {
+            (get_time() as f32 * 4.0).sin() * 0.5 + 0.5
+        };
 
         let edges = self.board.get_raw_paths(coord);
         for edge in [HexEdge::XY, HexEdge::ZY, HexEdge::ZX] {
             let edgepos = EdgePos::new_raw(coord, edge);
             let mouse_matches = if let Some(mouse_edge) = mouse_edge {
                 mouse_edge == edgepos
-                    && self.board.can_twiddle_path(&puzzle, mouse_edge)
+                    && self.board.can_twiddle_path(puzzle, mouse_edge)
             } else {
                 false
             };
             let opacity = match (edges.contains(edge), mouse_matches) {
                 (true, false) => Some(1.0),
-                (true, true) => Some(
-                    ((get_time() as f32 * 4.0).sin() * 0.5 + 0.5) * 0.2 + 0.8,
-                ),
-                (false, true) => Some(
-                    ((get_time() as f32 * 4.0).sin() * 0.5 + 0.5) * 0.4 + 0.5,
-                ),
+                (true, true) => Some(pulse * 0.2 + 0.8),
+                (false, true) => Some(pulse * 0.4 + 0.5),
                 (false, false) => None,
             };
 
@@ -160,8 +175,15 @@ impl StateGameplay {
         }
     }
 
-    fn draw_ui(&self, res: &Resources, level: &Level) {
-        patch9(16.0, 8.0, HEIGHT - 48.0, 19, 4, res.textures.billboard);
+    fn draw_ui(&self, res: &Resources) {
+        patch9(
+            16.0,
+            BILLBOARD.x,
+            BILLBOARD.y,
+            19,
+            4,
+            res.textures.billboard,
+        );
 
         for (idx, b) in [&self.b_check, &self.b_back, &self.b_help]
             .iter()
@@ -179,12 +201,30 @@ impl StateGameplay {
                     ..Default::default()
                 },
             );
+            b.draw_focus_ring(BLACK);
         }
 
+        if self.touch_mode {
+            for b in self.b_fingers.iter() {
+                b.draw(
+                    hexcolor(0x75d9d0ff),
+                    hexcolor(0x42756bff),
+                    hexcolor(0x99e8e0ff),
+                    hexcolor(0x2eb368ff),
+                    1.0,
+                    res,
+                );
+            }
+        }
+
+        let reduced_motion = res.config.data.reduced_motion;
         let (alien_dy, sx) = match self.check_state {
             CheckState::Waiting => {
-                (if self.frames % 64 < 32 { 0.0 } else { 1.0 }, 0.0)
+                let bob = self.frames % 64 >= 32 && !reduced_motion;
+                (if bob { 1.0 } else { 0.0 }, 0.0)
             }
+            // Hold on the frowny face instead of flashing
+            CheckState::No(_) if reduced_motion => (0.0, 32.0),
             CheckState::No(time) => {
                 (0.0, if time % 16 < 8 { 16.0 } else { 32.0 })
             }
@@ -201,42 +241,88 @@ impl StateGameplay {
             },
         );
 
-        draw_pixel_text(
-            &level.description,
-            8.0 + 3.0,
-            HEIGHT - 48.0 + 5.0,
-            TextAlign::Left,
+        self.description
+            .draw(BILLBOARD.x + 3.0, BILLBOARD.y + 5.0, BLACK, res);
+    }
+}
+
+impl StateGameplay {
+    /// Time and moves for this attempt, with the best so far and par under
+    /// them, in the top right.
+    fn draw_stats(&self, res: &Resources, level: &Level) {
+        let font = res.textures.font;
+        let x = WIDTH - 3.0;
+        let mut y = if self.touch_mode {
+            // Under the finger buttons
+            3.0 + 4.0 * (FINGER_BUTTON_HEIGHT + 3.0)
+        } else {
+            3.0
+        };
+        let mut line = |text: &str, color: Color| {
+            draw_pixel_text(text, x, y, TextAlign::Right, color, res);
+            y += line_advance(font);
+        };
+
+        line(
+            &format!(
+                "{}  {} {}",
+                format_time(self.frames),
+                self.moves,
+                res.locales.ui("moves")
+            ),
             BLACK,
-            res.textures.font,
         );
+
+        let progress = self.progress(&res.save);
+        let best = progress.and_then(|p| Some((p.best_time?, p.best_moves?)));
+        let under_par = progress.is_some_and(|p| p.under_par);
+        if let Some((time, moves)) = best {
+            line(
+                &format!(
+                    "{} {}  {}",
+                    res.locales.ui("best"),
+                    format_time(time),
+                    moves
+                ),
+                BLACK,
+            );
+        }
+        if let Some(par) = level.par {
+            let mut text = res.locales.ui("par").to_owned();
+            if let Some(seconds) = par.seconds {
+                text += " ";
+                text += &format_time(seconds as u64 * UPDATES_PER_SECOND);
+            }
+            if let Some(moves) = par.moves {
+                text += &format!("  {}", moves);
+            }
+            let color = if under_par {
+                hexcolor(0x2eb368ff)
+            } else {
+                BLACK
+            };
+            line(&text, color);
+        }
+
+        if self.new_best && matches!(self.check_state, CheckState::Yes(_)) {
+            line(res.locales.ui("new_best"), hexcolor(0xcc2d47ff));
+        }
     }
 }
 
 fn draw_flank_numbers(
-    marks: &Vec<Vec<NonZeroU8>>,
+    marks: &[Vec<NonZeroU8>],
     level: &Level,
     dir: Direction,
     start: (f32, f32),
     deltas: (f32, f32),
-    res: &ResourcesRef,
+    res: &Resources,
 ) {
     'side: for (i, markset) in marks.iter().enumerate() {
         if markset.is_empty() {
             continue 'side;
         }
-        // ... -2, -1, 0, 1, 2 ...
-        let centered_idx = i as i32 - level.puzzle.radius() as i32;
-        let side_center = Coordinate::new(0, 0)
-            - (Coordinate::from(dir).scale(level.puzzle.radius() as i32));
-        let offset = Coordinate::from(
-            dir + if centered_idx > 0 {
-                Angle::Right
-            } else {
-                Angle::Left
-            },
-        )
-        .scale(centered_idx.abs());
-        let anchor = side_center + offset;
+        let anchor = level.puzzle.flank_anchor(dir, i);
         let anchorpos = coord_to_px(anchor) + Vec2::from(start);
 
         for (j, mark) in markset.iter().rev().enumerate() {
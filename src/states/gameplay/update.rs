@@ -1,37 +1,128 @@
 use gerrymander::Transition;
 use macroquad::prelude::*;
 
+use hex2d::Coordinate;
+
 use crate::{
-    resources::Resources, states::GameState, util::mouse_position_pixel,
+    button::tab_focus,
+    geom::EdgePos,
+    input::{Action, Input, Walk},
+    puzzle::{Board, Level, Puzzle},
+    resources::Resources,
+    save::Save,
+    sfx::{SoundEvent, SoundQueue},
+    states::{GameState, StateOptions},
+    svg,
 };
 
 use super::{
-    far_px_to_edge, px_to_edge, CheckState, StateGameplay, PATH_MIN_DIST,
+    description_box, far_px_to_edge, finger_buttons, CheckState, FingerButton,
+    StateGameplay, TouchGesture, BILLBOARD, PATH_MIN_DIST, TOUCH_DRAG_START,
+    TOUCH_PATH_MIN_DIST, UNDO_LIMIT, UPDATES_PER_SECOND,
 };
 
 impl StateGameplay {
-    pub(super) fn update_(&mut self) -> Transition<GameState> {
-        let res = Resources::get();
-        let level = &res
-            .levels
-            .get(self.level_idxs.0, self.level_idxs.1)
-            .unwrap();
-
-        if is_mouse_button_down(MouseButton::Left) {
-            if let Some(mouse_edge) =
-                far_px_to_edge(mouse_position_pixel(), PATH_MIN_DIST)
-            {
-                if self.board.can_twiddle_path(&level.puzzle, mouse_edge) {
-                    let set = *self.painting_path.get_or_insert_with(|| {
-                        let here =
-                            self.board.get_path(mouse_edge).unwrap_or_default();
-                        !here
-                    });
-                    self.board.set_path(&level.puzzle, mouse_edge, set);
-                }
+    pub(super) fn update_(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        let level = self.level(res);
+
+        if std::mem::take(&mut self.relabel) {
+            self.b_fingers = finger_buttons(res);
+            self.description =
+                description_box(res, self.pack, level, self.daily);
+        }
+
+        if self.recording {
+            res.recorder.record(input);
+        }
+
+        if !self.shape.fits(&level.puzzle) {
+            self.shape = level.puzzle.shape();
+            self.board = Board::default();
+            self.undo.clear();
+            self.redo.clear();
+            self.painting_path = None;
+            self.cursor = None;
+        }
+
+        self.mouse = input.mouse;
+
+        // The d-pad both walks and moves focus, and walking wins in here
+        if input.walk.is_none() {
+            tab_focus(
+                res,
+                input,
+                [&mut self.b_check, &mut self.b_back, &mut self.b_help],
+            );
+        }
+        let any_focused = [&self.b_check, &self.b_back, &self.b_help]
+            .iter()
+            .any(|b| b.is_focused());
+
+        self.description.update(res);
+        let advance = input.mouse_pressed && BILLBOARD.contains(input.mouse)
+            || !any_focused && input.pressed(Action::NextPage);
+        if advance && self.description.advance() {
+            // Start over from the top so it can be read again
+            self.description.rewind();
+        }
+
+        // Touches also show up as the mouse, so only one of these at a time
+        if input.touches.is_empty() {
+            if input.mouse_down {
+                let (puzzle, mouse) = (&level.puzzle, input.mouse);
+                self.stroke_to(&res.sfx, puzzle, mouse, PATH_MIN_DIST);
+            } else {
+                self.end_stroke();
             }
         } else {
-            self.painting_path = None;
+            self.touch_mode = true;
+        }
+        self.update_touch(&res.sfx, &level.puzzle, &input.touches);
+        if input.mouse_pressed {
+            self.cursor = None;
+        }
+
+        if let Some(Walk { dir, draw }) = input.walk {
+            match self.cursor {
+                // First press just shows where the cursor is
+                None => {
+                    self.cursor = Coordinate::new(0, 0)
+                        .range_iter(level.puzzle.radius() as i32)
+                        .find(|c| level.puzzle.is_valid(*c));
+                }
+                Some(cursor) if level.puzzle.is_valid(cursor + dir) => {
+                    if draw {
+                        let edge = EdgePos::new(cursor, dir);
+                        let set =
+                            !self.board.get_path(edge).unwrap_or_default();
+                        self.remember();
+                        self.paint_path(&res.sfx, &level.puzzle, edge, set);
+                        self.unsaved = true;
+                    }
+                    self.cursor = Some(cursor + dir);
+                }
+                Some(_) => {}
+            }
+        }
+
+        let fingers = self
+            .b_fingers
+            .each_ref()
+            .map(|b| self.touch_mode && b.pressed(input));
+        let finger = |b: FingerButton| fingers[b as usize];
+        if input.pressed(Action::Undo) || finger(FingerButton::Undo) {
+            self.undo(&res.sfx);
+        } else if input.pressed(Action::Redo) || finger(FingerButton::Redo) {
+            self.redo(&res.sfx);
+        }
+
+        // Replays shouldn't leave files lying around
+        if input.pressed(Action::Export) && self.recording {
+            export_svg(&res.sfx, self.pack_id.as_deref(), level, &self.board);
         }
 
         if let CheckState::No(timer) | CheckState::Yes(timer) =
@@ -39,7 +130,9 @@ impl StateGameplay {
         {
             if *timer == 0 {
                 if matches!(self.check_state, CheckState::Yes(_)) {
-                    // TODO: pop
+                    self.leave(res);
+                    res.recorder.stop();
+                    return Transition::Pop;
                 } else {
                     self.check_state = CheckState::Waiting;
                 }
@@ -48,23 +141,345 @@ impl StateGameplay {
             }
         }
 
-        if is_key_pressed(KeyCode::Space)
-            || self.b_check.mouse_hovering()
-                && is_mouse_button_down(MouseButton::Left)
+        if input.pressed(Action::Check)
+            || finger(FingerButton::Check)
+            || self.b_check.activated(input)
+            || self.b_check.hovered_by(input) && input.mouse_down
         {
             let status = self.board.is_solved(&level.puzzle);
             self.check_state = match status {
-                Err(_) => CheckState::No(100),
-                Ok(()) => CheckState::Yes(120),
+                Err(_) if matches!(self.check_state, CheckState::No(_)) => {
+                    self.check_state
+                }
+                Err(_) => {
+                    res.sfx.emit(SoundEvent::CheckFailed);
+                    CheckState::No(100)
+                }
+                Ok(()) if matches!(self.check_state, CheckState::Yes(_)) => {
+                    self.check_state
+                }
+                Ok(()) => {
+                    if self.recording {
+                        let seconds =
+                            self.frames as f32 / UPDATES_PER_SECOND as f32;
+                        let under_par = level
+                            .par
+                            .is_some_and(|par| par.is_met(seconds, self.moves));
+                        let save = &mut res.save;
+                        self.new_best = self.progress_mut(save).record_solve(
+                            self.frames,
+                            self.moves,
+                            under_par,
+                        );
+                        save.commit();
+                    }
+                    res.sfx.emit(SoundEvent::CheckPassed);
+                    CheckState::Yes(120)
+                }
             };
         }
 
-        for b in [&mut self.b_check, &mut self.b_back, &mut self.b_help] {
-            b.post_update();
+        if input.pressed(Action::Pause) {
+            self.leave(res);
+            self.relabel = true;
+            return Transition::Push(GameState::Options(StateOptions::new(
+                res,
+            )));
+        }
+
+        if input.pressed(Action::Back)
+            || finger(FingerButton::Back)
+            || self.b_back.pressed(input)
+        {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            self.leave(res);
+            res.recorder.stop();
+            return Transition::Pop;
         }
 
-        self.frames += 1;
+        for b in [&mut self.b_check, &mut self.b_back, &mut self.b_help]
+            .into_iter()
+            .chain(self.b_fingers.iter_mut())
+        {
+            b.post_update(res, input);
+        }
+
+        // Stop the clock once it's solved
+        if !matches!(self.check_state, CheckState::Yes(_)) {
+            self.frames += 1;
+        }
+        self.save_board(&mut res.save);
 
         Transition::None
     }
+
+    /// Paint whatever edge is under `px`, starting a new stroke if there
+    /// isn't one going.
+    fn stroke_to(
+        &mut self,
+        sfx: &SoundQueue,
+        puzzle: &Puzzle,
+        px: Vec2,
+        tolerance: f32,
+    ) {
+        let Some(edge) = far_px_to_edge(px, tolerance) else {
+            return;
+        };
+        if !self.board.can_twiddle_path(puzzle, edge) {
+            return;
+        }
+        let set = match self.painting_path {
+            Some(set) => set,
+            None => {
+                self.remember();
+                let here = self.board.get_path(edge).unwrap_or_default();
+                *self.painting_path.insert(!here)
+            }
+        };
+        self.paint_path(sfx, puzzle, edge, set);
+    }
+
+    fn end_stroke(&mut self) {
+        if self.painting_path.take().is_some() {
+            // Finished a stroke, so remember where we're at
+            self.unsaved = true;
+        }
+    }
+
+    /// One finger drags to draw, and a second finger coming down undoes.
+    fn update_touch(
+        &mut self,
+        sfx: &SoundQueue,
+        puzzle: &Puzzle,
+        touches: &[Touch],
+    ) {
+        let down = touches
+            .iter()
+            .filter(|t| {
+                !matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+            })
+            .collect::<Vec<_>>();
+
+        if down.len() >= 2 {
+            if self.touch != TouchGesture::MultiFinger {
+                // Whatever the first finger was doing was a mistake
+                self.end_stroke();
+                self.undo(sfx);
+                self.touch = TouchGesture::MultiFinger;
+            }
+            return;
+        }
+
+        let find = |id: u64| down.iter().find(|t| t.id == id);
+        self.touch = match self.touch {
+            TouchGesture::Idle => match down.first() {
+                Some(t)
+                    if t.phase == TouchPhase::Started
+                        && !self.over_touch_ui(t.position) =>
+                {
+                    TouchGesture::Holding {
+                        id: t.id,
+                        start: t.position,
+                    }
+                }
+                _ => TouchGesture::Idle,
+            },
+            TouchGesture::Holding { id, start } => match find(id) {
+                Some(t) if t.position.distance(start) >= TOUCH_DRAG_START => {
+                    self.stroke_to(sfx, puzzle, start, TOUCH_PATH_MIN_DIST);
+                    self.stroke_to(
+                        sfx,
+                        puzzle,
+                        t.position,
+                        TOUCH_PATH_MIN_DIST,
+                    );
+                    TouchGesture::Drawing { id }
+                }
+                Some(_) => TouchGesture::Holding { id, start },
+                // Just a tap
+                None => TouchGesture::Idle,
+            },
+            TouchGesture::Drawing { id } => match find(id) {
+                Some(t) => {
+                    self.stroke_to(
+                        sfx,
+                        puzzle,
+                        t.position,
+                        TOUCH_PATH_MIN_DIST,
+                    );
+                    TouchGesture::Drawing { id }
+                }
+                None => {
+                    self.end_stroke();
+                    TouchGesture::Idle
+                }
+            },
+            TouchGesture::MultiFinger if down.is_empty() => TouchGesture::Idle,
+            TouchGesture::MultiFinger => TouchGesture::MultiFinger,
+        };
+    }
+
+    /// Is this spot taken up by something a finger could be pressing?
+    fn over_touch_ui(&self, px: Vec2) -> bool {
+        BILLBOARD.contains(px)
+            || self.touch_mode
+                && self.b_fingers.iter().any(|b| b.bounds().contains(px))
+    }
+
+    /// Snapshot the board so the next change can be undone.
+    fn remember(&mut self) {
+        if self.undo.len() >= UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(self.board.clone());
+        self.redo.clear();
+    }
+
+    fn undo(&mut self, sfx: &SoundQueue) {
+        if let Some(prev) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.board, prev));
+            sfx.emit(SoundEvent::EdgeOff);
+            self.unsaved = true;
+        }
+    }
+
+    fn redo(&mut self, sfx: &SoundQueue) {
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.board, next));
+            sfx.emit(SoundEvent::EdgeOn);
+            self.unsaved = true;
+        }
+    }
+
+    /// Turn a path on or off, counting it as a move if it changed.
+    fn paint_path(
+        &mut self,
+        sfx: &SoundQueue,
+        puzzle: &Puzzle,
+        edge: EdgePos,
+        set: bool,
+    ) {
+        let prev = self.board.set_path(puzzle, edge, set);
+        if prev.is_some_and(|prev| prev != set) {
+            self.moves += 1;
+            sfx.emit(if set {
+                SoundEvent::EdgeOn
+            } else {
+                SoundEvent::EdgeOff
+            });
+        }
+    }
+
+    /// Write the board to the save, if it's changed since last time.
+    fn save_board(&mut self, save: &mut Save) {
+        if !std::mem::take(&mut self.unsaved) || !self.recording {
+            return;
+        }
+        let progress = self.progress_mut(save);
+        progress.board = Some(self.board.clone());
+        progress.frames = self.frames;
+        progress.moves = self.moves;
+        save.commit();
+    }
+
+    /// Save everything on the way out, including the time spent since the
+    /// last edit.
+    fn leave(&mut self, res: &mut Resources) {
+        self.unsaved = true;
+        self.save_board(&mut res.save);
+        if self.recording {
+            res.recorder.store();
+        }
+    }
+}
+
+/// Write the puzzle and board out to the working directory, for design
+/// reviews and printed playtests.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_svg(
+    sfx: &SoundQueue,
+    pack_id: Option<&str>,
+    level: &Level,
+    board: &Board,
+) {
+    use std::io::Write;
+
+    // Packs can reuse the main game's level ids
+    let stem = match pack_id {
+        Some(pack) => format!("{}-{}", pack, level.id),
+        None => level.id.clone(),
+    };
+    let res = create_new_file(&stem, "svg").and_then(|(path, mut file)| {
+        file.write_all(svg::to_svg(&level.puzzle, board).as_bytes())
+            .map(|()| path)
+    });
+    match res {
+        Ok(path) => {
+            info!("exported {}", path);
+            sfx.emit(SoundEvent::ButtonClick);
+        }
+        Err(e) => warn!("couldn't export {}: {}", stem, e),
+    }
+}
+
+/// Make `<stem>.<ext>`, or `<stem>-2.<ext>` and so on if that's taken, so
+/// earlier exports are kept.
+#[cfg(not(target_arch = "wasm32"))]
+fn create_new_file(
+    stem: &str,
+    ext: &str,
+) -> std::io::Result<(String, std::fs::File)> {
+    for n in 1.. {
+        let path = if n == 1 {
+            format!("{}.{}", stem, ext)
+        } else {
+            format!("{}-{}.{}", stem, n, ext)
+        };
+        match std::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn export_svg(
+    _sfx: &SoundQueue,
+    _pack_id: Option<&str>,
+    _level: &Level,
+    _board: &Board,
+) {
+    warn!("can't export from the browser");
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_dont_overwrite_each_other() {
+        let dir = std::env::temp_dir().join("crop-circles-export-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let stem = dir.join("pack-level").to_str().unwrap().to_owned();
+
+        let paths = (0..3)
+            .map(|_| create_new_file(&stem, "svg").unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                format!("{}.svg", stem),
+                format!("{}-2.svg", stem),
+                format!("{}-3.svg", stem)
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,25 +1,359 @@
-use crate::{button::Button, resources::Resources};
+use gerrymander::Transition;
+use macroquad::prelude::*;
+
+use crate::{
+    button::{tab_focus, Button},
+    input::{Action, Input},
+    music::Track,
+    resources::Resources,
+    sfx::SoundEvent,
+    text::{draw_pixel_text, measure_line, TextAlign},
+    util::{hexcolor, patch9},
+    HEIGHT, WIDTH,
+};
+
+use super::{
+    GameState, GameStateDispatch, StateCutscene, StateDaily, StateGameplay,
+    StateOptions,
+};
+
+const BUTTON_SIZE: f32 = 8.0;
+const ROW_SPACING: f32 = 32.0;
+const COL_SPACING: f32 = 12.0;
+const TOP_Y: f32 = 40.0;
 
 pub struct StateLevelSelect {
-    buttons: Vec<Button>,
+    /// Index into `Resources::packs`, or `None` for the built-in levels
+    pack: Option<usize>,
+    buttons: Vec<AButton>,
+    b_options: Button,
+    /// Back out to the pack picker, if there is one
+    b_back: Option<Button>,
+    /// Only with the built-in levels
+    b_daily: Option<Button>,
+    /// The language might have changed while we were in the options
+    relabel: bool,
 }
 
 impl StateLevelSelect {
-    pub fn new() -> Self {
+    pub fn new(res: &Resources, pack: Option<usize>) -> Self {
         let mut buttons = Vec::new();
-        
-        let res = Resources::get();
-        for (row, levels) in res.levels.rows.iter().enumerate() {
-            let bx = 60 + row * 
-            
-            buttons.push(CutsceneButton {
-                button: todo!(),
-                row,
-            })
+        let rows = &res.pack_levels(pack).rows;
+        let left_x = ((WIDTH - (rows.len() as f32 - 1.0) * ROW_SPACING) / 2.0
+            - BUTTON_SIZE / 2.0)
+            .round();
+        for (row, levels) in rows.iter().enumerate() {
+            let bx = left_x + row as f32 * ROW_SPACING;
+
+            // The story beat leading into this row. Packs don't have any.
+            if pack.is_none() && res.cutscenes.get(row).is_some() {
+                buttons.push(AButton::Cutscene(CutsceneButton {
+                    button: Button::new(
+                        bx,
+                        TOP_Y,
+                        BUTTON_SIZE,
+                        BUTTON_SIZE,
+                        None,
+                    ),
+                    row,
+                }));
+            }
+            for col in 0..levels.len() {
+                let by = TOP_Y + (col + 2) as f32 * COL_SPACING;
+                buttons.push(AButton::Level(LevelButton {
+                    button: Button::new(bx, by, BUTTON_SIZE, BUTTON_SIZE, None),
+                    row,
+                    col,
+                }));
+            }
+        }
+
+        let b_options = options_button(res);
+        let b_back = res.any_packs().then(|| back_button(res));
+        let b_daily = pack.is_none().then(|| daily_button(res));
+
+        Self {
+            pack,
+            buttons,
+            b_options,
+            b_back,
+            b_daily,
+            relabel: false,
         }
     }
 }
 
+fn back_button(res: &Resources) -> Button {
+    let label = res.locales.ui("back");
+    let w = measure_line(label, res.textures.font) + 4.0;
+    Button::new(
+        16.0,
+        HEIGHT - 28.0,
+        w,
+        9.0,
+        Some((label.to_owned(), TextAlign::Center)),
+    )
+}
+
+fn daily_button(res: &Resources) -> Button {
+    let label = res.locales.ui("daily_puzzle");
+    let w = measure_line(label, res.textures.font) + 4.0;
+    Button::new(
+        ((WIDTH - w) / 2.0).round(),
+        HEIGHT - 28.0,
+        w,
+        9.0,
+        Some((label.to_owned(), TextAlign::Center)),
+    )
+}
+
+fn options_button(res: &Resources) -> Button {
+    let label = res.locales.ui("options");
+    let w = measure_line(label, res.textures.font) + 4.0;
+    Button::new(
+        WIDTH - 16.0 - w,
+        HEIGHT - 28.0,
+        w,
+        9.0,
+        Some((label.to_owned(), TextAlign::Center)),
+    )
+}
+
+impl GameStateDispatch for StateLevelSelect {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        tab_focus(
+            res,
+            input,
+            self.buttons
+                .iter_mut()
+                .map(AButton::get_button_mut)
+                .chain(self.b_daily.as_mut())
+                .chain(std::iter::once(&mut self.b_options))
+                .chain(self.b_back.as_mut()),
+        );
+
+        let clicked =
+            self.buttons.iter().find(|b| b.get_button().pressed(input));
+        if clicked.is_some() {
+            res.sfx.emit(SoundEvent::ButtonClick);
+        }
+        let trans = match clicked {
+            Some(AButton::Level(l)) => {
+                if level_status(res, self.pack, l.row, l.col).is_open() {
+                    Transition::Push(GameState::Gameplay(StateGameplay::new(
+                        res, self.pack, l.row, l.col,
+                    )))
+                } else {
+                    Transition::None
+                }
+            }
+            Some(AButton::Cutscene(c)) => {
+                if level_status(res, self.pack, c.row, 0).is_open() {
+                    Transition::Push(GameState::Cutscene(StateCutscene::new(
+                        res, c.row,
+                    )))
+                } else {
+                    Transition::None
+                }
+            }
+            None => Transition::None,
+        };
+
+        if self.relabel {
+            let focused = self.b_options.is_focused();
+            self.b_options = options_button(res);
+            self.b_options.set_focused(focused);
+            if let Some(b_back) = &mut self.b_back {
+                let focused = b_back.is_focused();
+                *b_back = back_button(res);
+                b_back.set_focused(focused);
+            }
+            if let Some(b_daily) = &mut self.b_daily {
+                let focused = b_daily.is_focused();
+                *b_daily = daily_button(res);
+                b_daily.set_focused(focused);
+            }
+            self.relabel = false;
+        }
+        let trans = if matches!(trans, Transition::None)
+            && (self.b_options.pressed(input) || input.pressed(Action::Pause))
+        {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            self.relabel = true;
+            Transition::Push(GameState::Options(StateOptions::new(res)))
+        } else {
+            trans
+        };
+        let trans = match &self.b_daily {
+            Some(b_daily)
+                if matches!(trans, Transition::None)
+                    && b_daily.pressed(input) =>
+            {
+                res.sfx.emit(SoundEvent::ButtonClick);
+                Transition::Push(GameState::Daily(StateDaily::new(res)))
+            }
+            _ => trans,
+        };
+        let trans = match &self.b_back {
+            Some(b_back)
+                if matches!(trans, Transition::None)
+                    && (b_back.pressed(input)
+                        || input.pressed(Action::Back)) =>
+            {
+                res.sfx.emit(SoundEvent::ButtonClick);
+                Transition::Pop
+            }
+            _ => trans,
+        };
+
+        for b in self.buttons.iter_mut() {
+            b.get_button_mut().post_update(res, input);
+        }
+        self.b_options.post_update(res, input);
+        for b in self.b_back.iter_mut().chain(self.b_daily.as_mut()) {
+            b.post_update(res, input);
+        }
+
+        trans
+    }
+
+    fn draw(&self, res: &Resources) {
+        let pack = self.pack.map(|idx| &res.packs[idx]);
+        clear_background(
+            pack.and_then(|p| p.tint)
+                .unwrap_or(Color::from_rgba(139, 179, 146, 255)),
+        );
+        patch9(16.0, 8.0, 8.0, 19, 14, res.textures.billboard);
+
+        draw_pixel_text(
+            pack.map_or(res.locales.ui("select_starset"), |p| &p.name),
+            WIDTH / 2.0,
+            20.0,
+            TextAlign::Center,
+            BLACK,
+            res,
+        );
+
+        for b in self.buttons.iter() {
+            let button = b.get_button();
+            let (sx, label) = match b {
+                AButton::Level(l) => {
+                    let status = level_status(res, self.pack, l.row, l.col);
+                    let sx = match status {
+                        LevelStatus::Locked => 16.0,
+                        LevelStatus::Unlocked => 0.0,
+                        LevelStatus::Solved => 8.0,
+                    };
+                    (sx, Some(l.col + 1))
+                }
+                AButton::Cutscene(c) => {
+                    let sx = if level_status(res, self.pack, c.row, 0).is_open()
+                    {
+                        24.0
+                    } else {
+                        16.0
+                    };
+                    (sx, None)
+                }
+            };
+            let sy = if button.mouse_hovering() { 8.0 } else { 0.0 };
+            draw_texture_ex(
+                res.textures.level_select_buttons,
+                button.x(),
+                button.y(),
+                WHITE,
+                DrawTextureParams {
+                    source: Some(Rect::new(sx, sy, BUTTON_SIZE, BUTTON_SIZE)),
+                    ..Default::default()
+                },
+            );
+            button.draw_focus_ring(BLACK);
+
+            if let Some(label) = label {
+                draw_pixel_text(
+                    &label.to_string(),
+                    button.x() - 3.0,
+                    button.y() + 2.0,
+                    TextAlign::Right,
+                    BLACK,
+                    res,
+                );
+            }
+        }
+
+        for b in std::iter::once(&self.b_options)
+            .chain(&self.b_back)
+            .chain(&self.b_daily)
+        {
+            b.draw(
+                hexcolor(0x75d9d0ff),
+                hexcolor(0x42756bff),
+                hexcolor(0x99e8e0ff),
+                hexcolor(0x2eb368ff),
+                1.0,
+                res,
+            );
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Menu)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LevelStatus {
+    Locked,
+    Unlocked,
+    Solved,
+}
+
+impl LevelStatus {
+    fn is_open(&self) -> bool {
+        !matches!(self, LevelStatus::Locked)
+    }
+}
+
+/// A level is open once the one before it is solved, and the first level
+/// of each row opens up once every level in the row above is solved. Levels
+/// the solver can't find a way through don't need solving to get past, once
+/// they're open.
+fn level_status(
+    res: &Resources,
+    pack: Option<usize>,
+    row: usize,
+    col: usize,
+) -> LevelStatus {
+    let save = &res.save;
+    let id = res.pack_id(pack);
+    if save.is_solved(id, row, col) {
+        return LevelStatus::Solved;
+    }
+
+    let levels = res.pack_levels(pack);
+    let cleared = |row, col| match level_status(res, pack, row, col) {
+        LevelStatus::Solved => true,
+        LevelStatus::Unlocked => !levels.is_solvable(row, col),
+        LevelStatus::Locked => false,
+    };
+    let open = if col > 0 {
+        cleared(row, col - 1)
+    } else if row > 0 {
+        (0..levels.rows[row - 1].len()).all(|c| cleared(row - 1, c))
+    } else {
+        true
+    };
+    if open {
+        LevelStatus::Unlocked
+    } else {
+        LevelStatus::Locked
+    }
+}
+
 struct LevelButton {
     button: Button,
     row: usize,
@@ -51,3 +385,40 @@ impl AButton {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::Levels;
+
+    #[test]
+    fn unsolvable_levels_dont_lock_the_rest() {
+        let mut res = Resources::stub();
+        // The middle one has more marks than its line has cells
+        res.levels = Levels::new(
+            json5::from_str(
+                "[[
+                    { id: 'a', puzzle: { radius: 1, marks: [
+                        [[2, 2], [], [2, 2]],
+                        [[2, 2], [], [2, 2]],
+                        [[2, 2], [], [2, 2]],
+                    ] } },
+                    { id: 'b', puzzle: { radius: 1, marks: [
+                        [[1, 1, 1], [], []], [[], [], []], [[], [], []],
+                    ] } },
+                    { id: 'c', puzzle: { radius: 1, marks: [
+                        [[], [], []], [[], [], []], [[], [], []],
+                    ] } },
+                ]]",
+            )
+            .unwrap(),
+        );
+        let status = |res: &Resources, col| level_status(res, None, 0, col);
+
+        assert!(matches!(status(&res, 1), LevelStatus::Locked));
+        assert!(matches!(status(&res, 2), LevelStatus::Locked));
+        res.save.progress_mut(None, 0, 0).solved = true;
+        assert!(matches!(status(&res, 1), LevelStatus::Unlocked));
+        assert!(matches!(status(&res, 2), LevelStatus::Unlocked));
+    }
+}
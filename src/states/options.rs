@@ -0,0 +1,265 @@
+use gerrymander::Transition;
+use macroquad::prelude::*;
+
+use crate::{
+    button::{tab_focus, Button},
+    config::apply_fullscreen,
+    input::{Action, Input},
+    music::Track,
+    resources::Resources,
+    sfx::SoundEvent,
+    text::{draw_pixel_text, TextAlign},
+    util::{hexcolor, patch9},
+    WIDTH,
+};
+
+use super::{GameState, GameStateDispatch};
+
+const TOP_Y: f32 = 40.0;
+const ROW_SPACING: f32 = 14.0;
+const LABEL_X: f32 = 40.0;
+const VALUE_X: f32 = 200.0;
+const VALUE_WIDTH: f32 = 80.0;
+/// How much one click changes a volume by
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    IntegerScaling,
+    ReducedMotion,
+    Colorblind,
+    Language,
+}
+
+impl Setting {
+    const ALL: [Setting; 7] = [
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::Fullscreen,
+        Setting::IntegerScaling,
+        Setting::ReducedMotion,
+        Setting::Colorblind,
+        Setting::Language,
+    ];
+
+    fn ui_key(self) -> &'static str {
+        match self {
+            Setting::MusicVolume => "music_volume",
+            Setting::SfxVolume => "sfx_volume",
+            Setting::Fullscreen => "fullscreen",
+            Setting::IntegerScaling => "integer_scaling",
+            Setting::ReducedMotion => "reduced_motion",
+            Setting::Colorblind => "colorblind",
+            Setting::Language => "language",
+        }
+    }
+}
+
+/// Left-click a setting to go forward through its values, right-click to go
+/// back. From the keyboard, Enter or Right goes forward and Left goes back.
+///
+/// Doubles as the pause menu.
+pub struct StateOptions {
+    /// One for each of `Setting::ALL`
+    b_values: Vec<Button>,
+    b_back: Button,
+}
+
+impl StateOptions {
+    pub fn new(res: &Resources) -> Self {
+        let b_values = value_buttons(res);
+        let b_back = Button::new(
+            VALUE_X,
+            TOP_Y + (Setting::ALL.len() as f32 + 1.0) * ROW_SPACING,
+            VALUE_WIDTH,
+            9.0,
+            Some((res.locales.ui("back").to_owned(), TextAlign::Center)),
+        );
+
+        Self { b_values, b_back }
+    }
+
+    fn change(
+        &mut self,
+        res: &mut Resources,
+        setting: Setting,
+        forwards: bool,
+    ) {
+        let data = &mut res.config.data;
+        match setting {
+            Setting::MusicVolume => {
+                data.music_volume = step_volume(data.music_volume, forwards)
+            }
+            Setting::SfxVolume => {
+                data.sfx_volume = step_volume(data.sfx_volume, forwards)
+            }
+            Setting::Fullscreen => {
+                data.fullscreen = !data.fullscreen;
+                apply_fullscreen(data.fullscreen);
+            }
+            Setting::IntegerScaling => {
+                data.integer_scaling = !data.integer_scaling
+            }
+            Setting::ReducedMotion => {
+                data.reduced_motion = !data.reduced_motion
+            }
+            Setting::Colorblind => data.colorblind = !data.colorblind,
+            Setting::Language => {
+                if forwards {
+                    res.locales.cycle();
+                } else {
+                    res.locales.cycle_back();
+                }
+                data.language = Some(res.locales.current_code().to_owned());
+            }
+        }
+
+        self.b_values = value_buttons(res);
+        if setting == Setting::Language {
+            let focused = self.b_back.is_focused();
+            self.b_back = Button::new_from_rect(
+                self.b_back.bounds(),
+                Some((res.locales.ui("back").to_owned(), TextAlign::Center)),
+            );
+            self.b_back.set_focused(focused);
+        }
+    }
+}
+
+/// Go up or down a step, wrapping around at the ends.
+fn step_volume(volume: f32, forwards: bool) -> f32 {
+    let steps = (1.0 / VOLUME_STEP).round() as i32;
+    let current = (volume / VOLUME_STEP).round() as i32;
+    let next = if forwards { current + 1 } else { current - 1 };
+    next.rem_euclid(steps + 1) as f32 * VOLUME_STEP
+}
+
+fn value_buttons(res: &Resources) -> Vec<Button> {
+    let data = &res.config.data;
+    let on_off = |on: bool| res.locales.ui(if on { "on" } else { "off" });
+    Setting::ALL
+        .iter()
+        .enumerate()
+        .map(|(idx, setting)| {
+            let label = match setting {
+                Setting::MusicVolume => {
+                    format!("{}%", (data.music_volume * 100.0).round())
+                }
+                Setting::SfxVolume => {
+                    format!("{}%", (data.sfx_volume * 100.0).round())
+                }
+                Setting::Fullscreen => on_off(data.fullscreen).to_owned(),
+                Setting::IntegerScaling => {
+                    on_off(data.integer_scaling).to_owned()
+                }
+                Setting::ReducedMotion => {
+                    on_off(data.reduced_motion).to_owned()
+                }
+                Setting::Colorblind => on_off(data.colorblind).to_owned(),
+                Setting::Language => res.locales.current().name.clone(),
+            };
+            Button::new(
+                VALUE_X,
+                TOP_Y + idx as f32 * ROW_SPACING - 2.0,
+                VALUE_WIDTH,
+                9.0,
+                Some((label, TextAlign::Center)),
+            )
+        })
+        .collect()
+}
+
+impl GameStateDispatch for StateOptions {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        tab_focus(
+            res,
+            input,
+            self.b_values
+                .iter_mut()
+                .chain(std::iter::once(&mut self.b_back)),
+        );
+
+        // Which way to change a setting, if at all
+        let change = self.b_values.iter().enumerate().find_map(|(idx, b)| {
+            let forwards = b.pressed(input)
+                || b.is_focused() && input.pressed(Action::Increase);
+            let backwards = b.hovered_by(input) && input.right_pressed
+                || b.is_focused() && input.pressed(Action::Decrease);
+            if forwards || backwards {
+                Some((idx, forwards))
+            } else {
+                None
+            }
+        });
+        if let Some((idx, forwards)) = change {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            let focused = self.b_values[idx].is_focused();
+            self.change(res, Setting::ALL[idx], forwards);
+            self.b_values[idx].set_focused(focused);
+        }
+
+        let back = input.pressed(Action::Back)
+            || input.pressed(Action::Pause)
+            || self.b_back.pressed(input);
+
+        for b in self.b_values.iter_mut() {
+            b.post_update(res, input);
+        }
+        self.b_back.post_update(res, input);
+
+        if back {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            res.config.commit();
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, res: &Resources) {
+        clear_background(Color::from_rgba(139, 179, 146, 255));
+        patch9(16.0, 8.0, 8.0, 19, 14, res.textures.billboard);
+
+        draw_pixel_text(
+            res.locales.ui("options"),
+            WIDTH / 2.0,
+            20.0,
+            TextAlign::Center,
+            BLACK,
+            res,
+        );
+
+        for (idx, setting) in Setting::ALL.iter().enumerate() {
+            draw_pixel_text(
+                res.locales.ui(setting.ui_key()),
+                LABEL_X,
+                TOP_Y + idx as f32 * ROW_SPACING,
+                TextAlign::Left,
+                BLACK,
+                res,
+            );
+        }
+
+        for b in self.b_values.iter().chain(std::iter::once(&self.b_back)) {
+            b.draw(
+                hexcolor(0x75d9d0ff),
+                hexcolor(0x42756bff),
+                hexcolor(0x99e8e0ff),
+                hexcolor(0x2eb368ff),
+                1.0,
+                res,
+            );
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Menu)
+    }
+}
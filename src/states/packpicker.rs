@@ -0,0 +1,182 @@
+use gerrymander::Transition;
+use macroquad::prelude::*;
+
+use crate::{
+    button::{tab_focus, Button},
+    input::{Action, Input},
+    music::Track,
+    resources::Resources,
+    sfx::SoundEvent,
+    text::{char_advance, draw_pixel_text, TextAlign},
+    util::{hexcolor, patch9},
+    WIDTH,
+};
+
+use super::{GameState, GameStateDispatch, StateLevelSelect, StateOptions};
+
+const TOP_Y: f32 = 40.0;
+const ROW_SPACING: f32 = 14.0;
+const BUTTON_X: f32 = 32.0;
+const BUTTON_WIDTH: f32 = 120.0;
+const AUTHOR_X: f32 = 160.0;
+const SOLVED_X: f32 = WIDTH - 32.0;
+
+/// Choose between the built-in levels and the level packs. Only shows up
+/// if there are any packs. Broken ones are listed at the bottom with
+/// what's wrong with them.
+pub struct StatePackPicker {
+    /// The built-in levels, then one for each of `Resources::packs`
+    b_packs: Vec<Button>,
+    /// The language might have changed while we were in the options
+    relabel: bool,
+}
+
+impl StatePackPicker {
+    pub fn new(res: &Resources) -> Self {
+        Self {
+            b_packs: pack_buttons(res),
+            relabel: false,
+        }
+    }
+}
+
+fn pack_buttons(res: &Resources) -> Vec<Button> {
+    std::iter::once(res.locales.ui("main_levels"))
+        .chain(res.packs.iter().map(|p| p.name.as_str()))
+        .enumerate()
+        .map(|(idx, name)| {
+            Button::new(
+                BUTTON_X,
+                TOP_Y + idx as f32 * ROW_SPACING - 2.0,
+                BUTTON_WIDTH,
+                9.0,
+                Some((name.to_owned(), TextAlign::Center)),
+            )
+        })
+        .collect()
+}
+
+impl GameStateDispatch for StatePackPicker {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        if self.relabel {
+            let focused = self.b_packs.iter().position(|b| b.is_focused());
+            self.b_packs = pack_buttons(res);
+            if let Some(idx) = focused {
+                self.b_packs[idx].set_focused(true);
+            }
+            self.relabel = false;
+        }
+
+        tab_focus(res, input, self.b_packs.iter_mut());
+
+        let picked = self.b_packs.iter().position(|b| b.pressed(input));
+        for b in self.b_packs.iter_mut() {
+            b.post_update(res, input);
+        }
+
+        if let Some(idx) = picked {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            // The built-in levels come first
+            let pack = idx.checked_sub(1);
+            Transition::Push(GameState::LevelSelect(StateLevelSelect::new(
+                res, pack,
+            )))
+        } else if input.pressed(Action::Pause) {
+            res.sfx.emit(SoundEvent::ButtonClick);
+            self.relabel = true;
+            Transition::Push(GameState::Options(StateOptions::new(res)))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, res: &Resources) {
+        let save = &res.save;
+
+        clear_background(Color::from_rgba(139, 179, 146, 255));
+        patch9(16.0, 8.0, 8.0, 19, 14, res.textures.billboard);
+
+        draw_pixel_text(
+            res.locales.ui("level_packs"),
+            WIDTH / 2.0,
+            20.0,
+            TextAlign::Center,
+            BLACK,
+            res,
+        );
+
+        for (idx, b) in self.b_packs.iter().enumerate() {
+            b.draw(
+                hexcolor(0x75d9d0ff),
+                hexcolor(0x42756bff),
+                hexcolor(0x99e8e0ff),
+                hexcolor(0x2eb368ff),
+                1.0,
+                res,
+            );
+
+            let pack = idx.checked_sub(1);
+            let y = TOP_Y + idx as f32 * ROW_SPACING;
+            if let Some(pack) = pack {
+                draw_pixel_text(
+                    &res.packs[pack].author,
+                    AUTHOR_X,
+                    y,
+                    TextAlign::Left,
+                    BLACK,
+                    res,
+                );
+            }
+
+            let id = res.pack_id(pack);
+            let rows = &res.pack_levels(pack).rows;
+            let total = rows.iter().map(Vec::len).sum::<usize>();
+            let solved = rows
+                .iter()
+                .enumerate()
+                .flat_map(|(row, cols)| (0..cols.len()).map(move |c| (row, c)))
+                .filter(|&(row, col)| save.is_solved(id, row, col))
+                .count();
+            draw_pixel_text(
+                &format!("{}/{}", solved, total),
+                SOLVED_X,
+                y,
+                TextAlign::Right,
+                BLACK,
+                res,
+            );
+        }
+
+        let fits =
+            ((SOLVED_X - BUTTON_X) / char_advance(res.textures.font)) as usize;
+        for (idx, err) in res.broken_packs.iter().enumerate() {
+            let y = TOP_Y + (self.b_packs.len() + idx) as f32 * ROW_SPACING;
+            let path = match err.location {
+                Some((row, col)) => format!("{}:{}:{}", err.path, row, col),
+                None => err.path.clone(),
+            };
+            let error = format!("{:#}", err.error).replace('\n', " ");
+            for (text, dy, color) in
+                [(path, 0.0, BLACK), (error, 6.0, hexcolor(0xcc2d47ff))]
+            {
+                let text = text.chars().take(fits).collect::<String>();
+                draw_pixel_text(
+                    &text,
+                    BUTTON_X,
+                    y + dy,
+                    TextAlign::Left,
+                    color,
+                    res,
+                );
+            }
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Menu)
+    }
+}
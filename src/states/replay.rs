@@ -0,0 +1,78 @@
+use gerrymander::Transition;
+use macroquad::prelude::*;
+
+use crate::{
+    input::{Action, Input, InputSource, ScriptedInput},
+    music::Track,
+    replay::Replay,
+    resources::Resources,
+    text::{draw_pixel_text, TextAlign},
+    util::hexcolor,
+    WIDTH,
+};
+
+use super::{GameState, GameStateDispatch, StateGameplay};
+
+/// Plays back a recorded attempt at a level. The player's own input only
+/// gets used to leave.
+pub struct StateReplay {
+    gameplay: StateGameplay,
+    script: ScriptedInput,
+    /// Has the recording run out, or left the level?
+    finished: bool,
+}
+
+impl StateReplay {
+    pub fn new(res: &mut Resources, replay: &Replay) -> eyre::Result<Self> {
+        Ok(Self {
+            gameplay: StateGameplay::replaying(res, replay)?,
+            script: replay.script(),
+            finished: false,
+        })
+    }
+}
+
+impl GameStateDispatch for StateReplay {
+    fn update(
+        &mut self,
+        res: &mut Resources,
+        input: &Input,
+    ) -> Transition<GameState> {
+        if input.pressed(Action::Back)
+            || input.pressed(Action::Pause)
+            || self.finished
+                && (input.mouse_pressed || !input.touches.is_empty())
+        {
+            return Transition::Pop;
+        }
+        if self.finished {
+            return Transition::None;
+        }
+
+        let recorded = self.script.next_input();
+        // The options menu never got recorded, so there's no going in it
+        if let Transition::Pop = self.gameplay.update(res, &recorded) {
+            self.finished = true;
+        }
+        if self.script.is_done() {
+            self.finished = true;
+        }
+        Transition::None
+    }
+
+    fn draw(&self, res: &Resources) {
+        self.gameplay.draw(res);
+        draw_pixel_text(
+            res.locales.ui("replay"),
+            WIDTH / 2.0,
+            3.0,
+            TextAlign::Center,
+            hexcolor(0xcc2d47ff),
+            res,
+        );
+    }
+
+    fn music(&self) -> Option<Track> {
+        self.gameplay.music()
+    }
+}
@@ -1,71 +1,608 @@
-//! Utilities for rendering text.
-
-use itertools::*;
-use macroquad::prelude::*;
-
-/// Number of printable characters in an ASCII charset (including the non-printing character).
-pub const CHARACTER_COUNT: usize = 96;
-
-/// Quick-and-dirty draw some text with the upper-left corner at the given position,
-/// with one pixel of space between each line and each char.
-pub fn draw_pixel_text(
-    text: &str,
-    cx: f32,
-    cy: f32,
-    align: TextAlign,
-    color: Color,
-    font: Texture2D,
-) {
-    let mut cursor_x = 0usize;
-    let mut cursor_y = 0usize;
-
-    let char_width = font.width() / CHARACTER_COUNT as f32;
-    let char_height = font.height();
-
-    let line_widths = text.lines().map(|s| s.len()).collect_vec();
-
-    for c in text.bytes() {
-        let slice_idx = match c {
-            b' '..=b'~' => c - 0x20,
-            b'\n' => {
-                cursor_x = 0;
-                cursor_y += 1;
-                continue;
-            }
-            // otherwise just do the non-printing character
-            _ => 127,
-        };
-        let sx = slice_idx as f32 * char_width;
-
-        let offset_prop = match align {
-            TextAlign::Left => 0.0,
-            TextAlign::Center => -0.5,
-            TextAlign::Right => -1.0,
-        };
-        let offset =
-            line_widths[cursor_y] as f32 * (char_width + 1.0) * offset_prop;
-
-        let x = cx + cursor_x as f32 * (char_width + 1.0) + offset;
-        let y = cy + cursor_y as f32 * (char_height + 1.0);
-
-        draw_texture_ex(
-            font,
-            x.round(),
-            y.round(),
-            color,
-            DrawTextureParams {
-                source: Some(Rect::new(sx, 0.0, char_width, char_height)),
-                ..Default::default()
-            },
-        );
-
-        cursor_x += 1;
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TextAlign {
-    Left,
-    Center,
-    Right,
-}
+//! Utilities for rendering text.
+
+use itertools::*;
+use macroquad::prelude::*;
+
+use crate::{resources::Resources, sfx::SoundEvent, util::hexcolor};
+
+/// Number of printable characters in an ASCII charset (including the non-printing character).
+pub const CHARACTER_COUNT: usize = 96;
+
+/// Glyphs in the font after the ASCII ones, in order.
+pub const EXTRA_CHARACTERS: [char; 50] = [
+    'À', 'Á', 'Â', 'Ä', 'Ã', 'Ç', 'È', 'É', 'Ê', 'Ë', 'Ì', 'Í', 'Î', 'Ï', 'Ñ',
+    'Ò', 'Ó', 'Ô', 'Ö', 'Õ', 'Ù', 'Ú', 'Û', 'Ü', 'à', 'á', 'â', 'ä', 'ã', 'ç',
+    'è', 'é', 'ê', 'ë', 'ì', 'í', 'î', 'ï', 'ñ', 'ò', 'ó', 'ô', 'ö', 'õ', 'ù',
+    'ú', 'û', 'ü', '¡', '¿',
+];
+
+/// Total number of glyphs in the font.
+pub const GLYPH_COUNT: usize = CHARACTER_COUNT + EXTRA_CHARACTERS.len();
+
+/// Quick-and-dirty draw some text with the upper-left corner at the given position,
+/// with one pixel of space between each line and each char.
+///
+/// The text can contain markup; see [`parse_markup`]. `color` is used for
+/// anything that doesn't have a color tag, and gets swapped in colorblind
+/// mode just the same.
+pub fn draw_pixel_text(
+    text: &str,
+    cx: f32,
+    cy: f32,
+    align: TextAlign,
+    color: Color,
+    res: &Resources,
+) {
+    draw_pixel_text_partial(text, usize::MAX, cx, cy, align, color, res);
+}
+
+/// Draw only the first `limit` visible characters of the text.
+fn draw_pixel_text_partial(
+    text: &str,
+    limit: usize,
+    cx: f32,
+    cy: f32,
+    align: TextAlign,
+    color: Color,
+    res: &Resources,
+) {
+    let mut cursor_x = 0usize;
+    let mut cursor_y = 0usize;
+
+    let font = res.textures.font;
+    let char_width = font.width() / GLYPH_COUNT as f32;
+    let char_height = font.height();
+
+    let reduced_motion = res.config.data.reduced_motion;
+    let colorblind = res.config.data.colorblind;
+
+    let glyphs = parse_markup(text);
+    let line_widths = glyphs
+        .split(|(c, _)| *c == '\n')
+        .map(|line| line.len())
+        .collect_vec();
+
+    for (idx, &(c, style)) in glyphs.iter().take(limit).enumerate() {
+        if c == '\n' {
+            cursor_x = 0;
+            cursor_y += 1;
+            continue;
+        }
+        let slice_idx = glyph_index(c);
+        let sx = slice_idx as f32 * char_width;
+
+        let offset_prop = match align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => -0.5,
+            TextAlign::Right => -1.0,
+        };
+        let offset =
+            line_widths[cursor_y] as f32 * (char_width + 1.0) * offset_prop;
+
+        let mut x = cx + cursor_x as f32 * (char_width + 1.0) + offset;
+        let mut y = cy + cursor_y as f32 * (char_height + 1.0);
+        if style.wave && !reduced_motion {
+            y += ((get_time() as f32 * 8.0 - idx as f32 * 0.6).sin() * 1.5)
+                .round();
+        }
+        if style.shake && !reduced_motion {
+            x += rand::gen_range(-1, 2) as f32;
+            y += rand::gen_range(-1, 2) as f32;
+        }
+
+        draw_texture_ex(
+            font,
+            x.round(),
+            y.round(),
+            match style.color.unwrap_or(color) {
+                c if colorblind => colorblind_swap(c),
+                c => c,
+            },
+            DrawTextureParams {
+                source: Some(Rect::new(sx, 0.0, char_width, char_height)),
+                ..Default::default()
+            },
+        );
+
+        cursor_x += 1;
+    }
+}
+
+/// Which glyph in the font draws the given character.
+fn glyph_index(c: char) -> usize {
+    match c {
+        ' '..='~' => c as usize - 0x20,
+        _ => match EXTRA_CHARACTERS.iter().position(|&extra| extra == c) {
+            Some(idx) => CHARACTER_COUNT + idx,
+            // otherwise just do the non-printing character
+            None => 0x7f - 0x20,
+        },
+    }
+}
+
+/// How far the cursor moves for each character, including spacing.
+pub fn char_advance(font: Texture2D) -> f32 {
+    font.width() / GLYPH_COUNT as f32 + 1.0
+}
+
+/// How far the cursor moves for each line, including spacing.
+pub fn line_advance(font: Texture2D) -> f32 {
+    font.height() + 1.0
+}
+
+/// How a run of text is drawn, as set by markup.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextStyle {
+    /// `None` to use whatever color the text is being drawn with
+    pub color: Option<Color>,
+    pub wave: bool,
+    pub shake: bool,
+}
+
+/// Split text into its visible characters and the style to draw each with.
+///
+/// Tags look like `{red}`, `{wave}` or `{shake}`, and `{/}` closes whichever
+/// tag was opened last. Unknown tags are ignored, and a `{` that isn't part
+/// of a tag is drawn as-is.
+pub fn parse_markup(text: &str) -> Vec<(char, TextStyle)> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut styles = vec![TextStyle::default()];
+
+    for (_, piece) in markup(text) {
+        match piece {
+            Markup::Glyph(c) => out.push((c, *styles.last().unwrap())),
+            Markup::Close => {
+                // Never pop the base style
+                if styles.len() > 1 {
+                    styles.pop();
+                }
+            }
+            Markup::Open(tag) => {
+                // Unknown tags still get pushed so their closing tag lines up
+                let mut style = *styles.last().unwrap();
+                match tag {
+                    "wave" => style.wave = true,
+                    "shake" => style.shake = true,
+                    _ => {
+                        if let Some(color) = named_color(tag) {
+                            style.color = Some(color);
+                        }
+                    }
+                }
+                styles.push(style);
+            }
+        }
+    }
+
+    out
+}
+
+/// One piece of marked-up text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Markup<'a> {
+    Glyph(char),
+    /// The tag's name, without the braces
+    Open(&'a str),
+    Close,
+}
+
+/// Split text into glyphs and tags, each with the byte it starts at.
+fn markup(text: &str) -> impl Iterator<Item = (usize, Markup<'_>)> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let rest = &text[at..];
+        let c = rest.chars().next()?;
+        let mut piece = (Markup::Glyph(c), c.len_utf8());
+        if let Some((tag, _)) =
+            rest.strip_prefix('{').and_then(|r| r.split_once('}'))
+        {
+            if tag == "/" {
+                piece = (Markup::Close, 3);
+            } else if !tag.is_empty()
+                && tag.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                piece = (Markup::Open(tag), tag.len() + 2);
+            }
+        }
+        at += piece.1;
+        Some((at - piece.1, piece.0))
+    })
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    let code = match name {
+        "red" => 0xcc2d47ff,
+        "pink" => 0xe36e64ff,
+        "green" => 0x2eb368ff,
+        "teal" => 0x48cfafff,
+        "gray" => 0x42756bff,
+        "white" => 0xffffffff,
+        "black" => 0x000000ff,
+        _ => return None,
+    };
+    Some(hexcolor(code))
+}
+
+/// Trade the red and green highlights for orange and blue, which are much
+/// harder to mix up.
+fn colorblind_swap(color: Color) -> Color {
+    if color == hexcolor(0xcc2d47ff) {
+        hexcolor(0xe08a1eff)
+    } else if color == hexcolor(0x2eb368ff) {
+        hexcolor(0x2d5fc4ff)
+    } else {
+        color
+    }
+}
+
+/// How wide a single line of text would be drawn, in pixels.
+///
+/// Markup doesn't take up any space.
+pub fn measure_line(line: &str, font: Texture2D) -> f32 {
+    let len = parse_markup(line).len();
+    if len == 0 {
+        0.0
+    } else {
+        len as f32 * char_advance(font) - 1.0
+    }
+}
+
+/// How big a block of text would be drawn, in pixels.
+pub fn measure_pixel_text(text: &str, font: Texture2D) -> Vec2 {
+    let width = text
+        .lines()
+        .map(|line| measure_line(line, font))
+        .fold(0.0, f32::max);
+    let line_count = text.lines().count();
+    let height = if line_count == 0 {
+        0.0
+    } else {
+        line_count as f32 * line_advance(font) - 1.0
+    };
+    vec2(width, height)
+}
+
+/// Break text into lines no wider than `max_width`, breaking between words
+/// where possible. Newlines already in the text are kept.
+///
+/// Tags still open at the end of a line are closed there and opened again
+/// on the next one, so each line can be drawn on its own.
+pub fn wrap_pixel_text(
+    text: &str,
+    max_width: f32,
+    font: Texture2D,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_owned()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if measure_line(&candidate, font) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                out.push(std::mem::take(&mut line));
+            }
+            // Words too long to fit on a line at all get chopped up
+            let mut word = word;
+            while measure_line(word, font) > max_width {
+                let fit = ((max_width + 1.0) / char_advance(font)).max(1.0);
+                // Cut after the last glyph that fits, so tags stay whole
+                let split = markup(word)
+                    .filter_map(|(i, piece)| match piece {
+                        Markup::Glyph(c) => Some(i + c.len_utf8()),
+                        _ => None,
+                    })
+                    .nth(fit as usize - 1)
+                    .unwrap_or(word.len());
+                out.push(word[..split].to_owned());
+                word = &word[split..];
+            }
+            line = word.to_owned();
+        }
+        out.push(line);
+    }
+
+    let mut open = Vec::new();
+    for line in out.iter_mut().filter(|line| !line.is_empty()) {
+        let mut carried = open
+            .iter()
+            .map(|tag| format!("{{{}}}", tag))
+            .collect::<String>();
+        for (_, piece) in markup(line) {
+            match piece {
+                Markup::Open(tag) => open.push(tag.to_owned()),
+                Markup::Close => {
+                    open.pop();
+                }
+                Markup::Glyph(_) => {}
+            }
+        }
+        carried.push_str(line);
+        carried.push_str(&"{/}".repeat(open.len()));
+        *line = carried;
+    }
+    out
+}
+
+/// How many characters get typed out between each blip.
+const BLIP_EVERY: usize = 3;
+
+/// A block of wrapped text spread across as many pages as it takes,
+/// optionally typing itself out a few characters at a time.
+#[derive(Debug, Clone)]
+pub struct TextBox {
+    /// Each page is its lines joined with newlines
+    pages: Vec<String>,
+    page: usize,
+    /// How many characters of the current page are showing
+    revealed: f32,
+    /// Characters revealed per frame, or `None` to show pages all at once
+    reveal_speed: Option<f32>,
+    width: f32,
+}
+
+impl TextBox {
+    pub fn new(
+        text: &str,
+        width: f32,
+        max_lines: usize,
+        font: Texture2D,
+    ) -> Self {
+        let lines = wrap_pixel_text(text, width, font);
+        let mut pages = lines
+            .chunks(max_lines.max(1))
+            .map(|page| {
+                // Don't start a page with the blank line between paragraphs
+                let start = page.iter().take_while(|l| l.is_empty()).count();
+                page[start..].join("\n")
+            })
+            .filter(|page| !page.is_empty())
+            .collect_vec();
+        if pages.is_empty() {
+            pages.push(String::new());
+        }
+
+        Self {
+            pages,
+            page: 0,
+            revealed: f32::INFINITY,
+            reveal_speed: None,
+            width,
+        }
+    }
+
+    /// Type the text out this many characters per frame.
+    pub fn with_reveal(mut self, chars_per_frame: f32) -> Self {
+        self.reveal_speed = Some(chars_per_frame);
+        self.revealed = 0.0;
+        self
+    }
+
+    /// Call once a frame to keep typing.
+    pub fn update(&mut self, res: &Resources) {
+        let Some(speed) = self.reveal_speed else {
+            return;
+        };
+        if !self.is_revealing() {
+            return;
+        }
+
+        let before = self.revealed as usize;
+        self.revealed += speed;
+        let after = self.revealed as usize;
+        // Blip every few letters, not every one
+        let glyphs = parse_markup(&self.pages[self.page]);
+        let blip = (before..after).any(|idx| {
+            idx % BLIP_EVERY == 0
+                && glyphs.get(idx).is_some_and(|(c, _)| !c.is_whitespace())
+        });
+        if blip {
+            res.sfx.emit(SoundEvent::TextBlip);
+        }
+    }
+
+    /// Is the current page still being typed out?
+    pub fn is_revealing(&self) -> bool {
+        (self.revealed as usize) < parse_markup(&self.pages[self.page]).len()
+    }
+
+    pub fn is_last_page(&self) -> bool {
+        self.page + 1 >= self.pages.len()
+    }
+
+    /// Finish typing the current page, or go to the next one.
+    ///
+    /// Returns `true` if there was nothing left to show.
+    pub fn advance(&mut self) -> bool {
+        if self.is_revealing() {
+            self.revealed = f32::INFINITY;
+            false
+        } else if self.is_last_page() {
+            true
+        } else {
+            self.page += 1;
+            self.revealed = if self.reveal_speed.is_some() {
+                0.0
+            } else {
+                f32::INFINITY
+            };
+            false
+        }
+    }
+
+    /// Go back to the first page.
+    pub fn rewind(&mut self) {
+        self.page = 0;
+        self.revealed = if self.reveal_speed.is_some() {
+            0.0
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    /// Draw the current page with the upper-left corner at the given position.
+    pub fn draw(&self, x: f32, y: f32, color: Color, res: &Resources) {
+        let font = res.textures.font;
+        let page = &self.pages[self.page];
+        draw_pixel_text_partial(
+            page,
+            self.revealed as usize,
+            x,
+            y,
+            TextAlign::Left,
+            color,
+            res,
+        );
+
+        // Blink an arrow in the corner when there's more to read
+        if !self.is_revealing()
+            && !self.is_last_page()
+            && get_time() % 1.0 < 0.5
+        {
+            let height = measure_pixel_text(page, font).y;
+            draw_pixel_text(
+                ">",
+                x + self.width,
+                y + height - font.height(),
+                TextAlign::Right,
+                color,
+                res,
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red() -> TextStyle {
+        TextStyle {
+            color: named_color("red"),
+            ..Default::default()
+        }
+    }
+
+    fn text(glyphs: &[(char, TextStyle)]) -> String {
+        glyphs.iter().map(|(c, _)| c).collect()
+    }
+
+    /// Every glyph keeps its style however the text gets broken up. Spaces and
+    /// newlines move around, so they don't count.
+    fn same_glyphs(lines: &[String], text: &str) {
+        let wrapped = lines.iter().flat_map(|line| parse_markup(line));
+        let unwrapped = parse_markup(text)
+            .into_iter()
+            .filter(|(c, _)| !c.is_whitespace());
+        assert!(
+            wrapped.filter(|(c, _)| !c.is_whitespace()).eq(unwrapped),
+            "{:?}",
+            lines
+        );
+    }
+
+    #[test]
+    fn nested_tags() {
+        let glyphs = parse_markup("{red}a{wave}b{/}c{/}d");
+        let wavy = TextStyle {
+            wave: true,
+            ..red()
+        };
+        assert_eq!(
+            glyphs,
+            [
+                ('a', red()),
+                ('b', wavy),
+                ('c', red()),
+                ('d', TextStyle::default())
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_closing_tags_do_nothing() {
+        let glyphs = parse_markup("{/}a{red}b{/}{/}c");
+        assert_eq!(
+            glyphs,
+            [
+                ('a', TextStyle::default()),
+                ('b', red()),
+                ('c', TextStyle::default())
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_tags_are_closed_like_any_other() {
+        let glyphs = parse_markup("{red}{blink}a{/}b{/}c");
+        assert_eq!(
+            glyphs,
+            [('a', red()), ('b', red()), ('c', TextStyle::default())]
+        );
+    }
+
+    #[test]
+    fn braces_that_arent_tags_are_drawn() {
+        for s in ["a { b", "{not a tag}", "{}", "{", "a}"] {
+            assert_eq!(text(&parse_markup(s)), s);
+        }
+    }
+
+    #[test]
+    fn markup_takes_no_width() {
+        let font = Texture2D::empty();
+        assert_eq!(
+            measure_line("{red}ab{wave}c{/}{/}", font),
+            measure_line("abc", font)
+        );
+        assert_eq!(measure_line("{red}{/}", font), 0.0);
+    }
+
+    #[test]
+    fn chopped_words_keep_their_style() {
+        let font = Texture2D::empty();
+        let width = measure_line("abc", font);
+        let lines = wrap_pixel_text("{shake}abcdef{/}", width, font);
+        assert_eq!(lines, ["{shake}abc{/}", "{shake}def{/}"]);
+        // Not cut partway through the tag
+        let mixed = "ab{red}cdef{/}";
+        let lines = wrap_pixel_text(mixed, width, font);
+        assert_eq!(lines, ["ab{red}c{/}", "{red}def{/}"]);
+        same_glyphs(&lines, mixed);
+    }
+
+    #[test]
+    fn styles_carry_over_to_the_next_page() {
+        let font = Texture2D::empty();
+        let said = "{shake}one two\nthree{/} four";
+        let width = measure_line("one two", font);
+        let lines = wrap_pixel_text(said, width, font);
+        same_glyphs(&lines, said);
+
+        let text_box = TextBox::new(said, width, 1, font);
+        assert_eq!(text_box.pages.len(), 3);
+        let shaking = |page: &str| {
+            parse_markup(page)
+                .iter()
+                .filter(|(_, style)| style.shake)
+                .map(|(c, _)| *c)
+                .collect::<String>()
+        };
+        assert_eq!(shaking(&text_box.pages[1]), "three");
+        assert_eq!(shaking(&text_box.pages[2]), "");
+    }
+}
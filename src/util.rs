@@ -2,15 +2,30 @@ use macroquad::prelude::*;
 
 use crate::{ASPECT_RATIO, HEIGHT, WIDTH};
 
-pub fn mouse_position_pixel() -> Vec2 {
-    let (mx, my) = mouse_position();
-    let (wd, hd) = wh_deficit();
-    let mx = (mx - wd / 2.0) / ((screen_width() - wd) / WIDTH);
-    let my = (my - hd / 2.0) / ((screen_height() - hd) / HEIGHT);
-    vec2(mx, my)
+/// Turn a position on the window into one on the canvas.
+pub fn screen_to_pixel(pos: Vec2, integer_scaling: bool) -> Vec2 {
+    let (wd, hd) = wh_deficit(integer_scaling);
+    let x = (pos.x - wd / 2.0) / ((screen_width() - wd) / WIDTH);
+    let y = (pos.y - hd / 2.0) / ((screen_height() - hd) / HEIGHT);
+    vec2(x, y)
 }
 
-pub fn wh_deficit() -> (f32, f32) {
+/// How much wider and taller the window is than the canvas drawn in it.
+///
+/// `integer_scaling` is the setting from the config.
+pub fn wh_deficit(integer_scaling: bool) -> (f32, f32) {
+    if integer_scaling {
+        // biggest whole-number scale that fits, but never smaller than 1x
+        let scale = (screen_width() / WIDTH)
+            .min(screen_height() / HEIGHT)
+            .floor()
+            .max(1.0);
+        return (
+            screen_width() - WIDTH * scale,
+            screen_height() - HEIGHT * scale,
+        );
+    }
+
     if (screen_width() / screen_height()) > ASPECT_RATIO {
         // it's too wide! put bars on the sides!
         // the height becomes the authority on how wide to draw